@@ -0,0 +1,280 @@
+use std::collections::HashSet;
+
+use eyre::{Result, bail};
+use futures_lite::StreamExt;
+use iroh_blobs::{
+    Hash, HashAndFormat,
+    api::{Store, Tag, blobs::BlobStatus},
+    store::GcConfig,
+};
+use n0_future::time::Duration;
+use person_protocol::unix_millis;
+use serde::{Deserialize, Serialize};
+
+use crate::persistence::PersistedStore;
+
+const TAG_PREFIX: &[u8] = b"pupu:";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlobConfig {
+    pub quota_bytes: Option<u64>,
+    pub gc_interval_secs: u64,
+}
+impl BlobConfig {
+    pub fn gc_interval(&self) -> Duration {
+        Duration::from_secs(self.gc_interval_secs.max(1))
+    }
+    pub fn gc_config(&self) -> GcConfig {
+        GcConfig {
+            interval: self.gc_interval(),
+            add_protected: None,
+        }
+    }
+}
+impl Default for BlobConfig {
+    fn default() -> Self {
+        Self {
+            quota_bytes: None,
+            gc_interval_secs: 60 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "id", rename_all = "snake_case")]
+pub enum BlobOwner {
+    Avatar(String),
+    Chat(String),
+    Group(String),
+}
+
+#[derive(Serialize, Deserialize)]
+struct TagMeta {
+    owner: BlobOwner,
+    hash: Hash,
+    created_at: u64,
+    expires_at: Option<u64>,
+}
+impl TagMeta {
+    fn name(&self) -> Result<Tag> {
+        Ok(Tag::from(
+            [TAG_PREFIX, &serde_json::to_vec(self)?].concat().as_slice(),
+        ))
+    }
+    fn parse(name: &Tag) -> Option<Self> {
+        serde_json::from_slice(name.0.strip_prefix(TAG_PREFIX)?).ok()
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BlobUsage {
+    pub total_bytes: u64,
+    pub quota_bytes: Option<u64>,
+    pub blob_count: usize,
+    pub avatar_bytes: u64,
+    pub chat_bytes: u64,
+    pub group_bytes: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct GcReport {
+    pub removed_tags: usize,
+}
+
+#[derive(Clone)]
+pub struct BlobTags {
+    store: Store,
+    config: BlobConfig,
+    persisted: Option<PersistedStore>,
+}
+impl BlobTags {
+    pub fn new(store: Store, config: BlobConfig, persisted: Option<PersistedStore>) -> Self {
+        Self {
+            store,
+            config,
+            persisted,
+        }
+    }
+    pub async fn available(&self) -> Result<Option<u64>> {
+        let Some(quota) = self.config.quota_bytes else {
+            return Ok(None);
+        };
+        Ok(Some(quota.saturating_sub(self.usage().await?.total_bytes)))
+    }
+    pub async fn check_quota(&self, size: u64) -> Result<()> {
+        if self.available().await?.is_some_and(|v| size > v) {
+            bail!("存储空间不足，超过存储配额");
+        }
+        Ok(())
+    }
+    pub async fn tag(
+        &self,
+        hash: Hash,
+        owner: BlobOwner,
+        expires_in_secs: Option<u64>,
+    ) -> Result<()> {
+        let now = unix_millis();
+        let meta = TagMeta {
+            owner,
+            hash,
+            created_at: now,
+            expires_at: expires_in_secs.map(|v| now.saturating_add(v.saturating_mul(1000))),
+        };
+        let name = meta.name()?;
+        self.store
+            .tags()
+            .set(name.clone(), HashAndFormat::raw(hash))
+            .await?;
+        if let Some(persisted) = &self.persisted {
+            persisted.put_blob(&self.store, hash).await?;
+            persisted.put_tag(&name, hash).await?;
+        }
+        Ok(())
+    }
+    async fn delete_tag(&self, name: Tag) -> Result<()> {
+        if let Some(persisted) = &self.persisted {
+            persisted.remove_tag(&name).await?;
+        }
+        self.store.tags().delete(name).await?;
+        Ok(())
+    }
+    pub async fn release(&self, owner: &BlobOwner) -> Result<usize> {
+        let mut released = 0;
+        for (name, meta) in self.list().await? {
+            if &meta.owner == owner {
+                self.delete_tag(name).await?;
+                released += 1;
+            }
+        }
+        Ok(released)
+    }
+    async fn list(&self) -> Result<Vec<(Tag, TagMeta)>> {
+        let mut tags = Vec::new();
+        let mut stream = self.store.tags().list_prefix(TAG_PREFIX).await?;
+        while let Some(info) = stream.next().await {
+            let info = info?;
+            if let Some(meta) = TagMeta::parse(&info.name) {
+                tags.push((info.name, meta));
+            }
+        }
+        Ok(tags)
+    }
+    async fn size(&self, hash: Hash) -> Result<u64> {
+        Ok(match self.store.blobs().status(hash).await? {
+            BlobStatus::Complete { size } => size,
+            BlobStatus::Partial { size } => size.unwrap_or_default(),
+            BlobStatus::NotFound => 0,
+        })
+    }
+    pub async fn usage(&self) -> Result<BlobUsage> {
+        let mut usage = BlobUsage {
+            quota_bytes: self.config.quota_bytes,
+            ..Default::default()
+        };
+        let mut counted = HashSet::new();
+        for (_, meta) in self.list().await? {
+            let size = self.size(meta.hash).await?;
+            match meta.owner {
+                BlobOwner::Avatar(_) => usage.avatar_bytes += size,
+                BlobOwner::Chat(_) => usage.chat_bytes += size,
+                BlobOwner::Group(_) => usage.group_bytes += size,
+            }
+            if counted.insert(meta.hash) {
+                usage.total_bytes += size;
+                usage.blob_count += 1;
+            }
+        }
+        Ok(usage)
+    }
+    pub async fn prune(&self) -> Result<GcReport> {
+        let mut report = GcReport::default();
+        let now = unix_millis();
+        for (name, meta) in self.list().await? {
+            if meta.expires_at.is_some_and(|v| v <= now) {
+                self.delete_tag(name).await?;
+                report.removed_tags += 1;
+            }
+        }
+        if let Some(persisted) = &self.persisted {
+            let mut tagged = HashSet::new();
+            let mut stream = self.store.tags().list().await?;
+            while let Some(info) = stream.next().await {
+                tagged.insert(info?.hash);
+            }
+            persisted.retain_blobs(&tagged).await?;
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_blobs::store::mem::MemStore;
+
+    use super::*;
+
+    async fn blob_tags(quota_bytes: Option<u64>) -> Result<(BlobTags, Hash)> {
+        let store = MemStore::new();
+        let hash = store.add_bytes(b"blob".to_vec()).await?.hash;
+        let blob_tags = BlobTags::new(
+            store.into(),
+            BlobConfig {
+                quota_bytes,
+                ..Default::default()
+            },
+            None,
+        );
+        Ok((blob_tags, hash))
+    }
+
+    #[tokio::test]
+    async fn prune_removes_expired_tags() -> Result<()> {
+        let (blob_tags, hash) = blob_tags(None).await?;
+        blob_tags
+            .tag(hash, BlobOwner::Chat("a".to_string()), Some(0))
+            .await?;
+        blob_tags
+            .tag(hash, BlobOwner::Chat("b".to_string()), Some(60))
+            .await?;
+        blob_tags
+            .tag(hash, BlobOwner::Group("c".to_string()), None)
+            .await?;
+        assert_eq!(blob_tags.prune().await?.removed_tags, 1);
+        let owners = blob_tags
+            .list()
+            .await?
+            .into_iter()
+            .map(|(_, meta)| meta.owner)
+            .collect::<Vec<_>>();
+        assert_eq!(owners.len(), 2);
+        assert!(!owners.contains(&BlobOwner::Chat("a".to_string())));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn prune_keeps_live_tags_over_quota() -> Result<()> {
+        let (blob_tags, hash) = blob_tags(Some(1)).await?;
+        blob_tags
+            .tag(hash, BlobOwner::Chat("a".to_string()), None)
+            .await?;
+        blob_tags
+            .tag(hash, BlobOwner::Group("b".to_string()), Some(60))
+            .await?;
+        assert_eq!(blob_tags.prune().await?.removed_tags, 0);
+        assert_eq!(blob_tags.list().await?.len(), 2);
+        assert!(blob_tags.check_quota(1).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tag_saturates_far_expiry() -> Result<()> {
+        let (blob_tags, hash) = blob_tags(None).await?;
+        blob_tags
+            .tag(hash, BlobOwner::Chat("a".to_string()), Some(u64::MAX))
+            .await?;
+        assert_eq!(blob_tags.list().await?[0].1.expires_at, Some(u64::MAX));
+        assert_eq!(blob_tags.prune().await?.removed_tags, 0);
+        Ok(())
+    }
+}
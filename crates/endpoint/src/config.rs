@@ -0,0 +1,187 @@
+use std::net::SocketAddr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::BlobConfig;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayConfig {
+    pub url: String,
+    pub quic_port: u16,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayModeConfig {
+    #[default]
+    DefaultAndCustom,
+    CustomOnly,
+    Disabled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AddressLookupConfig {
+    pub pkarr_publish: bool,
+    pub pkarr_resolve: bool,
+    pub pkarr_relay: Option<String>,
+    pub dns: bool,
+    pub dns_origin: Option<String>,
+    pub dns_server: Option<SocketAddr>,
+    pub mdns: bool,
+    pub dht: bool,
+}
+impl AddressLookupConfig {
+    pub fn lan_only() -> Self {
+        Self {
+            pkarr_publish: false,
+            pkarr_resolve: false,
+            pkarr_relay: None,
+            dns: false,
+            dns_origin: None,
+            dns_server: None,
+            mdns: true,
+            dht: false,
+        }
+    }
+}
+impl Default for AddressLookupConfig {
+    fn default() -> Self {
+        Self {
+            pkarr_publish: true,
+            pkarr_resolve: true,
+            pkarr_relay: None,
+            dns: true,
+            dns_origin: None,
+            dns_server: None,
+            mdns: true,
+            dht: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EndpointConfig {
+    pub address_lookup: AddressLookupConfig,
+    pub relay_mode: RelayModeConfig,
+    pub relays: Vec<RelayConfig>,
+    pub relay_only: bool,
+    pub person_protocol: person_protocol::Config,
+    pub blob: BlobConfig,
+}
+impl EndpointConfig {
+    pub fn lan_only(mut self) -> Self {
+        self.address_lookup = AddressLookupConfig::lan_only();
+        self.relay_mode = RelayModeConfig::Disabled;
+        self.relay_only = false;
+        self
+    }
+    pub fn address_lookup(mut self, address_lookup: AddressLookupConfig) -> Self {
+        self.address_lookup = address_lookup;
+        self
+    }
+    pub fn pkarr_relay(mut self, url: impl Into<String>) -> Self {
+        self.address_lookup.pkarr_relay = Some(url.into());
+        self
+    }
+    pub fn dns_origin(mut self, origin: impl Into<String>) -> Self {
+        self.address_lookup.dns_origin = Some(origin.into());
+        self
+    }
+    pub fn dns_server(mut self, server: SocketAddr) -> Self {
+        self.address_lookup.dns_server = Some(server);
+        self
+    }
+    pub fn relay_mode(mut self, relay_mode: RelayModeConfig) -> Self {
+        self.relay_mode = relay_mode;
+        self
+    }
+    pub fn relay_only(mut self, relay_only: bool) -> Self {
+        self.relay_only = relay_only;
+        self
+    }
+    pub fn relay(mut self, relay: RelayConfig) -> Self {
+        self.relays.push(relay);
+        self
+    }
+    pub fn person_protocol(mut self, config: person_protocol::Config) -> Self {
+        self.person_protocol = config;
+        self
+    }
+    pub fn blob(mut self, config: BlobConfig) -> Self {
+        self.blob = config;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_enable_public_lookups() {
+        let config = EndpointConfig::default();
+        let lookup = &config.address_lookup;
+        assert!(lookup.pkarr_publish && lookup.pkarr_resolve);
+        assert!(lookup.dns && lookup.mdns && lookup.dht);
+        assert_eq!(config.relay_mode, RelayModeConfig::DefaultAndCustom);
+        assert!(config.relays.is_empty());
+    }
+
+    #[test]
+    fn parse_partial_config() {
+        let config: EndpointConfig = serde_json::from_value(serde_json::json!({
+            "address_lookup": { "dht": false, "pkarr_relay": "https://pkarr.example.com" },
+            "relays": [{ "url": "https://relay.example.com", "quic_port": 7842 }],
+        }))
+        .unwrap();
+        assert!(!config.address_lookup.dht);
+        assert!(config.address_lookup.mdns);
+        assert_eq!(
+            config.address_lookup.pkarr_relay.as_deref(),
+            Some("https://pkarr.example.com")
+        );
+        assert_eq!(config.relay_mode, RelayModeConfig::DefaultAndCustom);
+        assert_eq!(config.relays.len(), 1);
+        assert_eq!(config.relays[0].quic_port, 7842);
+    }
+
+    #[test]
+    fn parse_empty_config() {
+        let config: EndpointConfig = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(config.address_lookup.pkarr_publish);
+        assert_eq!(config.relay_mode, RelayModeConfig::DefaultAndCustom);
+    }
+
+    #[test]
+    fn lan_only_uses_mdns() {
+        let config = EndpointConfig::default()
+            .relay(RelayConfig {
+                url: "https://relay.example.com".to_string(),
+                quic_port: 7842,
+            })
+            .lan_only();
+        let lookup = &config.address_lookup;
+        assert!(lookup.mdns);
+        assert!(!lookup.pkarr_publish && !lookup.pkarr_resolve);
+        assert!(!lookup.dns && !lookup.dht);
+        assert_eq!(config.relay_mode, RelayModeConfig::Disabled);
+        assert!(!config.relay_only);
+    }
+
+    #[test]
+    fn parse_relay_options() {
+        let config: EndpointConfig = serde_json::from_value(serde_json::json!({
+            "relay_mode": "custom_only",
+            "relay_only": true,
+        }))
+        .unwrap();
+        assert_eq!(config.relay_mode, RelayModeConfig::CustomOnly);
+        assert!(config.relay_only);
+        assert!(
+            serde_json::from_value::<EndpointConfig>(serde_json::json!({ "relay_mode": "other" }))
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use futures_lite::StreamExt;
+use iroh::{Endpoint, NetReport, Watcher};
+use n0_future::task::AbortOnDropHandle;
+use serde::Serialize;
+
+const CHANNEL_CAPACITY: usize = 32;
+
+// iroh 0.96.1 中 Endpoint::net_report 标记为 #[doc(hidden)]，升级 iroh 时只需检查此处
+fn net_report(endpoint: &Endpoint) -> impl Watcher<Value = Option<NetReport>> + use<> {
+    endpoint.net_report()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NatType {
+    Unknown,
+    EndpointIndependent,
+    EndpointDependent,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkReport {
+    pub online: bool,
+    pub home_relay: Option<String>,
+    pub direct_addrs: Vec<String>,
+    pub udp_v4: bool,
+    pub udp_v6: bool,
+    pub global_v4: Option<String>,
+    pub global_v6: Option<String>,
+    pub nat: NatType,
+    pub captive_portal: Option<bool>,
+}
+impl NetworkReport {
+    pub fn new(endpoint: &Endpoint) -> Self {
+        let addr = endpoint.addr();
+        let net_report = net_report(endpoint).get();
+        let home_relay = addr.relay_urls().next().map(|v| v.to_string());
+        let direct_addrs = addr.ip_addrs().map(|v| v.to_string()).collect::<Vec<_>>();
+        let mut report = Self {
+            online: home_relay.is_some() || !direct_addrs.is_empty(),
+            home_relay,
+            direct_addrs,
+            udp_v4: false,
+            udp_v6: false,
+            global_v4: None,
+            global_v6: None,
+            nat: NatType::Unknown,
+            captive_portal: None,
+        };
+        if let Some(net_report) = net_report {
+            report.udp_v4 = net_report.udp_v4;
+            report.udp_v6 = net_report.udp_v6;
+            report.global_v4 = net_report.global_v4.map(|v| v.to_string());
+            report.global_v6 = net_report.global_v6.map(|v| v.to_string());
+            report.nat = match net_report.mapping_varies_by_dest_ipv4 {
+                Some(true) => NatType::EndpointDependent,
+                Some(false) => NatType::EndpointIndependent,
+                None => NatType::Unknown,
+            };
+            report.captive_portal = net_report.captive_portal;
+            report.online = report.home_relay.is_some() || report.udp_v4 || report.udp_v6;
+        }
+        report
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConnectivityEvent {
+    HomeRelayChanged { relay: Option<String> },
+    DirectAddrsChanged { addrs: Vec<String> },
+    Offline,
+    Online,
+}
+
+pub struct Connectivity {
+    event_receiver: async_channel::Receiver<ConnectivityEvent>,
+    _task: AbortOnDropHandle<()>,
+}
+impl Connectivity {
+    pub fn new(endpoint: Endpoint) -> Self {
+        let (event_sender, event_receiver) = async_channel::bounded(CHANNEL_CAPACITY);
+        let task = n0_future::task::spawn(async move {
+            let mut changes = endpoint
+                .watch_addr()
+                .stream()
+                .map(|_| ())
+                .or(net_report(&endpoint).stream().map(|_| ()));
+            let mut last = NetworkReport::new(&endpoint);
+            while changes.next().await.is_some() {
+                let report = NetworkReport::new(&endpoint);
+                for event in changed_events(&last, &report) {
+                    log::info!("网络状态变化：{:?}", event);
+                    if event_sender.try_send(event).is_err() {
+                        log::debug!("网络事件队列已满，已丢弃事件");
+                    }
+                }
+                last = report;
+            }
+        });
+        Self {
+            event_receiver,
+            _task: AbortOnDropHandle::new(task),
+        }
+    }
+    pub fn event_receiver(&self) -> async_channel::Receiver<ConnectivityEvent> {
+        self.event_receiver.clone()
+    }
+}
+
+fn changed_events(last: &NetworkReport, report: &NetworkReport) -> Vec<ConnectivityEvent> {
+    let mut events = Vec::new();
+    if report.home_relay != last.home_relay {
+        events.push(ConnectivityEvent::HomeRelayChanged {
+            relay: report.home_relay.clone(),
+        });
+    }
+    if report.direct_addrs != last.direct_addrs {
+        events.push(ConnectivityEvent::DirectAddrsChanged {
+            addrs: report.direct_addrs.clone(),
+        });
+    }
+    if report.online != last.online {
+        events.push(if report.online {
+            ConnectivityEvent::Online
+        } else {
+            ConnectivityEvent::Offline
+        });
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(home_relay: Option<&str>, direct_addrs: &[&str]) -> NetworkReport {
+        NetworkReport {
+            online: home_relay.is_some() || !direct_addrs.is_empty(),
+            home_relay: home_relay.map(ToString::to_string),
+            direct_addrs: direct_addrs.iter().map(ToString::to_string).collect(),
+            udp_v4: false,
+            udp_v6: false,
+            global_v4: None,
+            global_v6: None,
+            nat: NatType::Unknown,
+            captive_portal: None,
+        }
+    }
+
+    #[test]
+    fn unchanged_report_emits_nothing() {
+        let last = report(Some("https://relay.example.com"), &["192.168.1.2:1234"]);
+        assert!(changed_events(&last, &last).is_empty());
+    }
+
+    #[test]
+    fn going_online_emits_changes() {
+        let events = changed_events(
+            &report(None, &[]),
+            &report(Some("https://relay.example.com"), &["192.168.1.2:1234"]),
+        );
+        assert_eq!(
+            events,
+            [
+                ConnectivityEvent::HomeRelayChanged {
+                    relay: Some("https://relay.example.com".to_string()),
+                },
+                ConnectivityEvent::DirectAddrsChanged {
+                    addrs: vec!["192.168.1.2:1234".to_string()],
+                },
+                ConnectivityEvent::Online,
+            ]
+        );
+    }
+
+    #[test]
+    fn going_offline_emits_offline() {
+        let events = changed_events(&report(None, &["192.168.1.2:1234"]), &report(None, &[]));
+        assert_eq!(
+            events,
+            [
+                ConnectivityEvent::DirectAddrsChanged { addrs: Vec::new() },
+                ConnectivityEvent::Offline,
+            ]
+        );
+    }
+
+    #[test]
+    fn relay_change_keeps_online() {
+        let events = changed_events(
+            &report(Some("https://a.example.com"), &[]),
+            &report(Some("https://b.example.com"), &[]),
+        );
+        assert_eq!(
+            events,
+            [ConnectivityEvent::HomeRelayChanged {
+                relay: Some("https://b.example.com".to_string()),
+            }]
+        );
+    }
+}
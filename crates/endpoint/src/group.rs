@@ -0,0 +1,342 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use eyre::{Result, bail};
+use futures_lite::StreamExt;
+use iroh::{EndpointId, SecretKey, Signature};
+use iroh_gossip::{
+    TopicId,
+    api::{Event, GossipReceiver, GossipSender},
+};
+use n0_future::task::AbortOnDropHandle;
+use parking_lot::Mutex;
+use person_protocol::{ChatMessage, unix_millis};
+use rkyv::{Archive, util::AlignedVec};
+use serde::Serialize;
+
+const CHANNEL_CAPACITY: usize = 64;
+const REPLAY_WINDOW: usize = 256;
+const MAX_TRACKED_AUTHORS: usize = 1024;
+const SIGNATURE_CONTEXT: &[u8] = b"pupu/group-message";
+
+#[derive(Serialize, Clone)]
+pub struct GroupMessage {
+    pub author: String,
+    pub sequence: u64,
+    pub message: ChatMessage,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "type", content = "id", rename_all = "snake_case")]
+pub enum MemberEvent {
+    Joined(String),
+    Left(String),
+}
+
+#[derive(Archive, rkyv::Serialize, rkyv::Deserialize)]
+struct Envelope {
+    author: [u8; 32],
+    sequence: u64,
+    payload: Vec<u8>,
+    signature: Vec<u8>,
+}
+impl Envelope {
+    fn sign(topic: TopicId, sequence: u64, payload: Vec<u8>, secret_key: &SecretKey) -> Self {
+        let author = *secret_key.public().as_bytes();
+        let signature = secret_key
+            .sign(&signing_bytes(topic, &author, sequence, &payload))
+            .to_bytes()
+            .to_vec();
+        Self {
+            author,
+            sequence,
+            payload,
+            signature,
+        }
+    }
+    fn open(self, topic: TopicId) -> Result<GroupMessage> {
+        let author = EndpointId::from_bytes(&self.author)?;
+        let signature = Signature::from_bytes(self.signature.as_slice().try_into()?);
+        if author
+            .verify(
+                &signing_bytes(topic, &self.author, self.sequence, &self.payload),
+                &signature,
+            )
+            .is_err()
+        {
+            bail!("群组消息签名验证失败");
+        }
+        Ok(GroupMessage {
+            author: author.to_string(),
+            sequence: self.sequence,
+            message: rkyv::from_bytes::<ChatMessage, rkyv::rancor::Error>(&aligned(&self.payload))?,
+        })
+    }
+}
+
+fn signing_bytes(topic: TopicId, author: &[u8; 32], sequence: u64, payload: &[u8]) -> Vec<u8> {
+    [
+        SIGNATURE_CONTEXT,
+        topic.as_bytes().as_slice(),
+        author.as_slice(),
+        &sequence.to_le_bytes(),
+        payload,
+    ]
+    .concat()
+}
+
+fn aligned(bytes: &[u8]) -> AlignedVec<16> {
+    let mut data = AlignedVec::<16>::new();
+    data.extend_from_slice(bytes);
+    data
+}
+
+#[derive(Default)]
+struct AuthorWindow {
+    last_used: u64,
+    sequences: BTreeSet<u64>,
+}
+
+#[derive(Default)]
+struct ReplayGuard {
+    seen: HashMap<String, AuthorWindow>,
+    clock: u64,
+}
+impl ReplayGuard {
+    fn check(&mut self, author: &str, sequence: u64) -> bool {
+        self.clock += 1;
+        if !self.seen.contains_key(author)
+            && self.seen.len() >= MAX_TRACKED_AUTHORS
+            && let Some(oldest) = self
+                .seen
+                .iter()
+                .min_by_key(|(_, v)| v.last_used)
+                .map(|(k, _)| k.clone())
+        {
+            self.seen.remove(&oldest);
+        }
+        let window = self.seen.entry(author.to_string()).or_default();
+        window.last_used = self.clock;
+        let seen = &mut window.sequences;
+        if seen.len() >= REPLAY_WINDOW && seen.first().is_some_and(|v| sequence <= *v) {
+            return false;
+        }
+        if !seen.insert(sequence) {
+            return false;
+        }
+        if seen.len() > REPLAY_WINDOW {
+            seen.pop_first();
+        }
+        true
+    }
+}
+
+pub struct Group {
+    topic: TopicId,
+    secret_key: SecretKey,
+    sender: GossipSender,
+    last_sequence: Mutex<u64>,
+    dropped: Arc<AtomicUsize>,
+    message_receiver: async_channel::Receiver<GroupMessage>,
+    member_event_receiver: async_channel::Receiver<MemberEvent>,
+    _task: AbortOnDropHandle<()>,
+}
+impl Group {
+    pub fn new(
+        topic: TopicId,
+        secret_key: SecretKey,
+        sender: GossipSender,
+        receiver: GossipReceiver,
+    ) -> Self {
+        let (message_sender, message_receiver) = async_channel::bounded(CHANNEL_CAPACITY);
+        let (member_event_sender, member_event_receiver) = async_channel::bounded(CHANNEL_CAPACITY);
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let task = n0_future::task::spawn(forward(
+            topic,
+            receiver,
+            message_sender,
+            member_event_sender,
+            dropped.clone(),
+        ));
+        Self {
+            topic,
+            secret_key,
+            sender,
+            last_sequence: Mutex::new(0),
+            dropped,
+            message_receiver,
+            member_event_receiver,
+            _task: AbortOnDropHandle::new(task),
+        }
+    }
+    pub fn sender(&self) -> GossipSender {
+        self.sender.clone()
+    }
+    pub fn seal(&self, message: &ChatMessage) -> Result<Vec<u8>> {
+        let sequence = {
+            let mut last_sequence = self.last_sequence.lock();
+            *last_sequence = unix_millis().max(*last_sequence + 1);
+            *last_sequence
+        };
+        let envelope = Envelope::sign(
+            self.topic,
+            sequence,
+            rkyv::to_bytes::<rkyv::rancor::Error>(message)?.to_vec(),
+            &self.secret_key,
+        );
+        Ok(rkyv::to_bytes::<rkyv::rancor::Error>(&envelope)?.to_vec())
+    }
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+    pub fn message_receiver(&self) -> async_channel::Receiver<GroupMessage> {
+        self.message_receiver.clone()
+    }
+    pub fn member_event_receiver(&self) -> async_channel::Receiver<MemberEvent> {
+        self.member_event_receiver.clone()
+    }
+}
+
+async fn forward(
+    topic: TopicId,
+    mut receiver: GossipReceiver,
+    message_sender: async_channel::Sender<GroupMessage>,
+    member_event_sender: async_channel::Sender<MemberEvent>,
+    dropped: Arc<AtomicUsize>,
+) {
+    let mut replay_guard = ReplayGuard::default();
+    while let Some(event) = receiver.next().await {
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => {
+                log::warn!("群组消息接收失败：{}", err);
+                break;
+            }
+        };
+        match event {
+            Event::Received(message) => {
+                let result =
+                    rkyv::from_bytes::<Envelope, rkyv::rancor::Error>(&aligned(&message.content))
+                        .map_err(eyre::Report::from)
+                        .and_then(|v| v.open(topic));
+                let message = match result {
+                    Ok(message) => message,
+                    Err(err) => {
+                        log::debug!("丢弃来自{}的无效群组消息：{}", message.delivered_from, err);
+                        dropped.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    }
+                };
+                if !replay_guard.check(&message.author, message.sequence) {
+                    log::debug!("丢弃{}重复发送的群组消息", message.author);
+                    dropped.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+                if message_sender.send(message).await.is_err() {
+                    break;
+                }
+            }
+            Event::NeighborUp(id) => {
+                let _ = member_event_sender.try_send(MemberEvent::Joined(id.to_string()));
+            }
+            Event::NeighborDown(id) => {
+                let _ = member_event_sender.try_send(MemberEvent::Left(id.to_string()));
+            }
+            Event::Lagged => log::warn!("群组消息处理过慢，部分消息已丢失"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope(topic: TopicId, sequence: u64, content: &str) -> Envelope {
+        let message = ChatMessage {
+            content: content.to_string(),
+        };
+        Envelope::sign(
+            topic,
+            sequence,
+            rkyv::to_bytes::<rkyv::rancor::Error>(&message)
+                .unwrap()
+                .to_vec(),
+            &SecretKey::from_bytes(&[1; 32]),
+        )
+    }
+
+    #[test]
+    fn envelope_round_trip() {
+        let topic = TopicId::from_bytes([7; 32]);
+        let message = envelope(topic, 42, "你好").open(topic).unwrap();
+        assert_eq!(
+            message.author,
+            SecretKey::from_bytes(&[1; 32]).public().to_string()
+        );
+        assert_eq!(message.sequence, 42);
+        assert_eq!(message.message.content, "你好");
+    }
+
+    #[test]
+    fn envelope_rejects_other_topic() {
+        let topic = TopicId::from_bytes([7; 32]);
+        assert!(
+            envelope(topic, 1, "你好")
+                .open(TopicId::from_bytes([8; 32]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn envelope_rejects_tampering() {
+        let topic = TopicId::from_bytes([7; 32]);
+        let mut tampered = envelope(topic, 1, "你好");
+        tampered.sequence += 1;
+        assert!(tampered.open(topic).is_err());
+        let mut tampered = envelope(topic, 1, "你好");
+        tampered.payload = envelope(topic, 1, "再见").payload;
+        assert!(tampered.open(topic).is_err());
+        let mut tampered = envelope(topic, 1, "你好");
+        tampered.author = *SecretKey::from_bytes(&[2; 32]).public().as_bytes();
+        assert!(tampered.open(topic).is_err());
+    }
+
+    #[test]
+    fn replay_guard_rejects_duplicates() {
+        let mut replay_guard = ReplayGuard::default();
+        assert!(replay_guard.check("a", 2));
+        assert!(replay_guard.check("a", 1));
+        assert!(!replay_guard.check("a", 2));
+        assert!(replay_guard.check("b", 2));
+    }
+
+    #[test]
+    fn replay_guard_rejects_sequences_below_window() {
+        let mut replay_guard = ReplayGuard::default();
+        for sequence in 1..=REPLAY_WINDOW as u64 + 1 {
+            assert!(replay_guard.check("a", sequence));
+        }
+        assert!(!replay_guard.check("a", 1));
+        assert!(!replay_guard.check("a", 2));
+        assert!(replay_guard.check("a", REPLAY_WINDOW as u64 + 2));
+    }
+
+    #[test]
+    fn replay_guard_evicts_least_recently_used_author() {
+        let mut replay_guard = ReplayGuard::default();
+        for author in 0..MAX_TRACKED_AUTHORS {
+            assert!(replay_guard.check(&author.to_string(), 1));
+        }
+        assert!(replay_guard.check("0", 2));
+        assert!(replay_guard.check("new", 1));
+        assert_eq!(replay_guard.seen.len(), MAX_TRACKED_AUTHORS);
+        assert!(!replay_guard.seen.contains_key("1"));
+        assert!(!replay_guard.check("0", 1));
+        assert!(!replay_guard.check("new", 1));
+    }
+}
@@ -0,0 +1,193 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use chacha20poly1305::{
+    ChaCha20Poly1305, Key, KeyInit, Nonce,
+    aead::{Aead, Payload},
+};
+use eyre::{Result, bail, eyre};
+use iroh::SecretKey;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+const KEYSTORE_VERSION: u8 = 1;
+const MIN_PASSPHRASE_LENGTH: usize = 8;
+const MAX_M_COST: u32 = 256 * 1024;
+const MAX_T_COST: u32 = 16;
+const MAX_P_COST: u32 = 8;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct KdfParams {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+impl KdfParams {
+    fn validate(&self) -> Result<()> {
+        let min = Self::default();
+        if !(min.m_cost..=MAX_M_COST).contains(&self.m_cost)
+            || !(min.t_cost..=MAX_T_COST).contains(&self.t_cost)
+            || !(min.p_cost..=MAX_P_COST).contains(&self.p_cost)
+        {
+            bail!("密钥文件中的密钥派生参数超出允许范围");
+        }
+        Ok(())
+    }
+    fn derive(&self, passphrase: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+        self.validate()?;
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|err| eyre!("密钥派生参数无效：{}", err))?;
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+            .map_err(|err| eyre!("密钥派生失败：{}", err))?;
+        Ok(key)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Keystore {
+    version: u8,
+    id: String,
+    kdf: KdfParams,
+    salt: [u8; 16],
+    nonce: [u8; 12],
+    ciphertext: Vec<u8>,
+}
+impl Keystore {
+    fn associated_data(&self) -> Vec<u8> {
+        [
+            &[self.version][..],
+            self.id.as_bytes(),
+            &self.kdf.m_cost.to_le_bytes(),
+            &self.kdf.t_cost.to_le_bytes(),
+            &self.kdf.p_cost.to_le_bytes(),
+        ]
+        .concat()
+    }
+    pub fn lock(secret_key: &SecretKey, passphrase: &str) -> Result<Self> {
+        if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+            bail!("密码长度不能少于{}个字符", MIN_PASSPHRASE_LENGTH);
+        }
+        let mut keystore = Self {
+            version: KEYSTORE_VERSION,
+            id: secret_key.public().to_string(),
+            kdf: KdfParams::default(),
+            salt: rand::random(),
+            nonce: rand::random(),
+            ciphertext: Vec::new(),
+        };
+        let key = keystore.kdf.derive(passphrase, &keystore.salt)?;
+        keystore.ciphertext = ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+            .encrypt(
+                Nonce::from_slice(&keystore.nonce),
+                Payload {
+                    msg: &secret_key.to_bytes(),
+                    aad: &keystore.associated_data(),
+                },
+            )
+            .map_err(|_| eyre!("密钥加密失败"))?;
+        Ok(keystore)
+    }
+    pub fn unlock(&self, passphrase: &str) -> Result<SecretKey> {
+        if self.version != KEYSTORE_VERSION {
+            bail!("不支持的密钥文件版本：{}", self.version);
+        }
+        let key = self.kdf.derive(passphrase, &self.salt)?;
+        let plaintext = Zeroizing::new(
+            ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+                .decrypt(
+                    Nonce::from_slice(&self.nonce),
+                    Payload {
+                        msg: &self.ciphertext,
+                        aad: &self.associated_data(),
+                    },
+                )
+                .map_err(|_| eyre!("密码错误或密钥文件已损坏"))?,
+        );
+        let secret_key = SecretKey::from_bytes(plaintext.as_slice().try_into()?);
+        if secret_key.public().to_string() != self.id {
+            bail!("密钥文件内容与标识不符");
+        }
+        Ok(secret_key)
+    }
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+    pub fn encode(&self) -> Result<String> {
+        Ok(BASE64_STANDARD.encode(serde_json::to_vec(self)?))
+    }
+    pub fn decode(keystore: &str) -> Result<Self> {
+        Ok(serde_json::from_slice(&BASE64_STANDARD.decode(keystore)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSPHRASE: &str = "correct horse";
+
+    fn keystore() -> Keystore {
+        Keystore::lock(&SecretKey::from_bytes(&[1; 32]), PASSPHRASE).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let keystore = Keystore::decode(&keystore().encode().unwrap()).unwrap();
+        let secret_key = keystore.unlock(PASSPHRASE).unwrap();
+        assert_eq!(secret_key.to_bytes(), [1; 32]);
+        assert_eq!(keystore.id(), secret_key.public().to_string());
+    }
+
+    #[test]
+    fn rejects_short_passphrase() {
+        assert!(Keystore::lock(&SecretKey::from_bytes(&[1; 32]), "short").is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_passphrase() {
+        assert!(keystore().unlock("wrong passphrase").is_err());
+    }
+
+    #[test]
+    fn rejects_tampering() {
+        let mut ciphertext = keystore();
+        ciphertext.ciphertext[0] ^= 1;
+        assert!(ciphertext.unlock(PASSPHRASE).is_err());
+        let mut id = keystore();
+        id.id = SecretKey::from_bytes(&[2; 32]).public().to_string();
+        assert!(id.unlock(PASSPHRASE).is_err());
+        let mut kdf = keystore();
+        kdf.kdf.t_cost += 1;
+        assert!(kdf.unlock(PASSPHRASE).is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_params() {
+        let mut keystore = keystore();
+        keystore.kdf.m_cost = u32::MAX;
+        assert!(keystore.unlock(PASSPHRASE).is_err());
+        keystore.kdf = KdfParams {
+            m_cost: 8,
+            t_cost: 1,
+            p_cost: 1,
+        };
+        assert!(keystore.unlock(PASSPHRASE).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let mut keystore = keystore();
+        keystore.version = 2;
+        assert!(keystore.unlock(PASSPHRASE).is_err());
+    }
+}
@@ -1,35 +1,120 @@
-use std::{path::Path, sync::Arc};
+mod blob;
+mod config;
+mod connectivity;
+mod group;
+mod keystore;
+mod pending;
+mod persistence;
+mod ticket;
 
-use base64::{Engine, prelude::BASE64_STANDARD};
-use eyre::Result;
+use std::sync::Arc;
+
+use eyre::{Result, bail, eyre};
+use futures_lite::StreamExt;
 use iroh::{
-    EndpointId, RelayMode, SecretKey,
-    address_lookup::{PkarrPublisher, PkarrResolver},
+    EndpointAddr, EndpointId, RelayMap, RelayMode, SecretKey,
+    address_lookup::{MemoryLookup, PkarrPublisher, PkarrResolver},
+    endpoint::Builder,
     endpoint::Connection,
     protocol::Router,
 };
-use iroh_blobs::{BlobsProtocol, api::Store};
-use iroh_gossip::{
-    Gossip, TopicId,
-    api::{GossipReceiver, GossipSender},
+use iroh_blobs::{
+    BlobFormat, BlobsProtocol, Hash, HashAndFormat,
+    api::{Store, TempTag, remote::GetProgressItem},
+    store::mem::{self, MemStore},
+    ticket::BlobTicket,
 };
+use iroh_gossip::{Gossip, TopicId};
 use iroh_relay::RelayQuicConfig;
-use parking_lot::Mutex;
-use person_protocol::{Person, PersonProtocol};
-use serde::{Deserialize, Serialize};
+use n0_future::task::AbortOnDropHandle;
+use person_protocol::{
+    Chat, ChatMessage, Code, ConnectionStats, FriendGreeting, Person, PersonProtocol, Presence,
+    Profile, ProfileTag,
+};
+use serde::Serialize;
 use sharded_slab::Slab;
 use utils::option_ext::OptionGet;
 
-#[derive(Serialize, Deserialize)]
-pub struct Ticket {
-    pub id: TopicId,
-    pub bootstrap: Vec<EndpointId>,
+use crate::{
+    blob::BlobTags, connectivity::Connectivity, group::Group, pending::PendingEvents,
+    persistence::PersistedStore,
+};
+pub use crate::{
+    blob::{BlobConfig, BlobOwner, BlobUsage, GcReport},
+    config::{AddressLookupConfig, EndpointConfig, RelayConfig, RelayModeConfig},
+    connectivity::{ConnectivityEvent, NatType, NetworkReport},
+    group::{GroupMessage, MemberEvent},
+    keystore::Keystore,
+    persistence::{
+        BlobPersistence, BoxFuture, MemoryPersistence, PersistenceEntries, SharedBlobPersistence,
+        StoreBackend,
+    },
+    ticket::{Ticket, TicketBody},
+};
+
+#[derive(Serialize)]
+#[serde(tag = "status", content = "value", rename_all = "snake_case")]
+pub enum Reply<T> {
+    Accepted(T),
+    Rejected(Code),
+}
+impl<T> From<Result<T, Code>> for Reply<T> {
+    fn from(value: Result<T, Code>) -> Self {
+        match value {
+            Ok(value) => Reply::Accepted(value),
+            Err(code) => Reply::Rejected(code),
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct RelayConfig {
-    url: String,
-    quic_port: u16,
+#[derive(Serialize)]
+#[serde(tag = "type")]
+pub enum EventInfo {
+    FriendRequest {
+        id: usize,
+        remote_id: String,
+        greeting: Option<FriendGreeting>,
+        expired: bool,
+    },
+    ChatRequest {
+        id: usize,
+        remote_id: String,
+        expired: bool,
+    },
+    PresenceUpdate {
+        id: usize,
+        remote_id: String,
+        presence: Presence,
+    },
+}
+impl EventInfo {
+    fn new(id: usize, event: &person_protocol::Event) -> Self {
+        match event {
+            person_protocol::Event::FriendRequest(friend_request) => EventInfo::FriendRequest {
+                id,
+                remote_id: friend_request.remote_id().to_string(),
+                greeting: friend_request.greeting().cloned(),
+                expired: friend_request.is_expired(),
+            },
+            person_protocol::Event::ChatRequest(chat_request) => EventInfo::ChatRequest {
+                id,
+                remote_id: chat_request.remote_id().to_string(),
+                expired: chat_request.is_expired(),
+            },
+            person_protocol::Event::PresenceUpdate(update) => EventInfo::PresenceUpdate {
+                id,
+                remote_id: update.remote_id().to_string(),
+                presence: update.presence(),
+            },
+        }
+    }
+}
+fn is_expired(event: &person_protocol::Event) -> bool {
+    match event {
+        person_protocol::Event::FriendRequest(friend_request) => friend_request.is_expired(),
+        person_protocol::Event::ChatRequest(chat_request) => chat_request.is_expired(),
+        person_protocol::Event::PresenceUpdate(_) => false,
+    }
 }
 
 #[derive(Clone)]
@@ -38,69 +123,92 @@ pub struct Endpoint {
     person_protocol: PersonProtocol,
     gossip_protocol: Gossip,
     _blobs_protocol: BlobsProtocol,
-    connection_pool: Arc<Slab<Connection>>,
-    person_protocol_event: Arc<Mutex<Option<person_protocol::Event>>>,
-    group_pool: Arc<Slab<(GossipSender, GossipReceiver)>>,
+    store: Store,
+    memory_lookup: MemoryLookup,
+    chat_pool: Arc<Slab<Chat>>,
+    pending_events: Arc<PendingEvents<person_protocol::Event>>,
+    group_pool: Arc<Slab<Group>>,
+    blob_tags: BlobTags,
+    connectivity: Arc<Connectivity>,
+    _gc_task: Arc<AbortOnDropHandle<()>>,
 }
 impl Endpoint {
     pub async fn new(
         secret_key: Vec<u8>,
         person: Person,
-        #[allow(unused_variables)] store_path: impl AsRef<Path>,
-        relay_configs: Vec<RelayConfig>,
+        store_backend: StoreBackend,
+        config: EndpointConfig,
     ) -> Result<Self> {
-        let relay_map = RelayMode::Default.relay_map();
-        for config in relay_configs {
-            relay_map.insert(
-                config.url.parse()?,
-                iroh::RelayConfig {
-                    url: config.url.parse()?,
-                    quic: Some(RelayQuicConfig {
-                        port: config.quic_port,
-                    }),
-                }
-                .into(),
-            );
+        let relay_mode = relay_mode(&config)?;
+        if config.relay_only && matches!(relay_mode, RelayMode::Disabled) {
+            bail!("仅中继模式需要至少一个可用的中继服务器");
         }
+        let memory_lookup = MemoryLookup::new();
         #[allow(unused_mut)]
-        let mut endpoint_builder = iroh::Endpoint::empty_builder(RelayMode::Custom(relay_map))
-            .address_lookup(PkarrPublisher::n0_dns())
-            .address_lookup(PkarrResolver::n0_dns());
+        let mut endpoint_builder = address_lookup(
+            iroh::Endpoint::empty_builder(relay_mode).address_lookup(memory_lookup.clone()),
+            &config.address_lookup,
+        )?;
         #[cfg(not(target_family = "wasm"))]
-        {
-            use iroh::address_lookup::{DhtAddressLookup, DnsAddressLookup, MdnsAddressLookup};
-
-            endpoint_builder = endpoint_builder
-                .address_lookup(MdnsAddressLookup::builder())
-                .address_lookup(DnsAddressLookup::n0_dns())
-                .address_lookup(DhtAddressLookup::builder());
+        if config.relay_only {
+            endpoint_builder = endpoint_builder.clear_ip_transports();
         }
         let endpoint = endpoint_builder
             .secret_key(SecretKey::from_bytes(secret_key.as_slice().try_into()?))
             .bind()
             .await?;
-        let person_protocol = PersonProtocol::new(endpoint.clone(), person);
+        let person_protocol =
+            PersonProtocol::new(endpoint.clone(), person, config.person_protocol)?;
         let gossip_protocol = Gossip::builder().spawn(endpoint.clone());
-        let store: Store;
-        #[cfg(not(target_family = "wasm"))]
-        {
-            use eyre::eyre;
-            use iroh_blobs::store::fs::FsStore;
+        let connectivity = Connectivity::new(endpoint.clone());
+        let (store, persisted): (Store, _) = match store_backend {
+            #[cfg(not(target_family = "wasm"))]
+            StoreBackend::Path(path) => {
+                use iroh_blobs::store::fs::{FsStore, options::Options};
 
-            store = FsStore::load(store_path.as_ref())
-                .await
-                .map_err(|err| eyre!(err))?
-                .into();
-        }
-        #[cfg(target_family = "wasm")]
-        {
-            use iroh_blobs::store::mem::MemStore;
-
-            store = MemStore::new().into();
-        }
+                let mut options = Options::new(&path);
+                options.gc = Some(config.blob.gc_config());
+                (
+                    FsStore::load_with_opts(path.join("blobs.db"), options)
+                        .await
+                        .map_err(|err| eyre!(err))?
+                        .into(),
+                    None,
+                )
+            }
+            #[cfg(target_family = "wasm")]
+            StoreBackend::Path(_) => bail!("当前平台不支持文件存储"),
+            StoreBackend::Memory => (
+                MemStore::new_with_opts(mem::Options {
+                    gc_config: Some(config.blob.gc_config()),
+                })
+                .into(),
+                None,
+            ),
+            StoreBackend::Persistence(persistence) => {
+                let (store, persisted) =
+                    PersistedStore::load(persistence, config.blob.gc_config()).await?;
+                (store, Some(persisted))
+            }
+        };
+        let blob_tags = BlobTags::new(store.clone(), config.blob.clone(), persisted);
+        let gc_interval = config.blob.gc_interval();
+        let gc_task = n0_future::task::spawn({
+            let blob_tags = blob_tags.clone();
+            async move {
+                loop {
+                    n0_future::time::sleep(gc_interval).await;
+                    match blob_tags.prune().await {
+                        Ok(report) => log::debug!("存储清理完成：{:?}", report),
+                        Err(err) => log::warn!("存储清理失败：{}", err),
+                    }
+                }
+            }
+        });
         let blobs_protocol = BlobsProtocol::new(&store, None);
         let router = Router::builder(endpoint)
-            .accept(person_protocol::ALPN, person_protocol.clone())
+            .accept(person_protocol::ALPN_V1, person_protocol.clone())
+            .accept(person_protocol::ALPN_V2, person_protocol.clone())
             .accept(iroh_gossip::ALPN, gossip_protocol.clone())
             .accept(iroh_blobs::ALPN, blobs_protocol.clone())
             .spawn();
@@ -109,9 +217,14 @@ impl Endpoint {
             person_protocol,
             gossip_protocol,
             _blobs_protocol: blobs_protocol,
-            connection_pool: Default::default(),
-            person_protocol_event: Default::default(),
+            store,
+            memory_lookup,
+            chat_pool: Default::default(),
+            pending_events: Default::default(),
             group_pool: Default::default(),
+            blob_tags,
+            connectivity: Arc::new(connectivity),
+            _gc_task: Arc::new(AbortOnDropHandle::new(gc_task)),
         })
     }
     pub async fn close(self) -> Result<()> {
@@ -121,60 +234,409 @@ impl Endpoint {
     pub fn id(&self) -> String {
         self.router.endpoint().id().to_string()
     }
-    pub async fn person_protocol_next_event(&self) -> Result<String> {
+    pub fn network_report(&self) -> NetworkReport {
+        NetworkReport::new(self.router.endpoint())
+    }
+    pub async fn next_connectivity_event(&self) -> Option<ConnectivityEvent> {
+        self.connectivity.event_receiver().recv().await.ok()
+    }
+    pub async fn person_protocol_next_event(&self) -> Result<EventInfo> {
         let event = self.person_protocol.next_event().await?;
-        let event_type = event.to_string();
-        self.person_protocol_event.lock().replace(event);
-        Ok(event_type)
-    }
-    pub fn person_protocol_event(&self, method: String) -> Result<serde_json::Value> {
-        match self.person_protocol_event.lock().take().get()? {
-            person_protocol::Event::FriendRequest(friend_request) => match method.as_ref() {
-                "remote_id" => return Ok(friend_request.remote_id().to_string().into()),
-                "accept" => friend_request.accept()?,
-                "reject" => friend_request.reject()?,
-                _ => (),
-            },
-            person_protocol::Event::ChatRequest(chat_request) => match method.as_ref() {
-                "remote_id" => return Ok(chat_request.remote_id().to_string().into()),
-                "accept" => {
-                    return Ok(self
-                        .connection_pool
-                        .insert(chat_request.accept()?)
-                        .get()?
-                        .into());
+        let id = self.pending_events.next_id();
+        let info = EventInfo::new(id, &event);
+        if !matches!(event, person_protocol::Event::PresenceUpdate(_)) {
+            self.pending_events.insert(id, event, is_expired);
+        }
+        Ok(info)
+    }
+    pub fn inspect_event(&self, id: usize) -> Result<EventInfo> {
+        self.pending_events
+            .inspect(id, |event| EventInfo::new(id, event))
+    }
+    pub fn accept_event(&self, id: usize) -> Result<Option<usize>> {
+        match self.pending_events.take(id)? {
+            person_protocol::Event::FriendRequest(friend_request) => {
+                friend_request.accept()?;
+                Ok(None)
+            }
+            person_protocol::Event::ChatRequest(chat_request) => {
+                Ok(Some(self.chat_pool.insert(chat_request.accept()?).get()?))
+            }
+            person_protocol::Event::PresenceUpdate(_) => Ok(None),
+        }
+    }
+    pub fn reject_event(&self, id: usize) -> Result<()> {
+        match self.pending_events.take(id)? {
+            person_protocol::Event::FriendRequest(friend_request) => friend_request.reject(),
+            person_protocol::Event::ChatRequest(chat_request) => chat_request.reject(),
+            person_protocol::Event::PresenceUpdate(_) => Ok(()),
+        }
+    }
+    pub fn profile(&self) -> Profile {
+        self.person_protocol.profile()
+    }
+    pub fn update_person(&self, person: Person) -> Result<()> {
+        self.person_protocol.update_person(person)
+    }
+    pub async fn set_avatar(&self, avatar: Vec<u8>) -> Result<String> {
+        let owner = BlobOwner::Avatar(self.id());
+        self.blob_tags.check_quota(avatar.len() as u64).await?;
+        let temp_tag = self.store.add_bytes(avatar).temp_tag().await?;
+        self.blob_tags.release(&owner).await?;
+        self.blob_tags.tag(temp_tag.hash(), owner, None).await?;
+        Ok(temp_tag.hash().to_string())
+    }
+    pub async fn fetch_avatar(&self, id: String, hash: String) -> Result<Vec<u8>> {
+        let hash = hash.parse::<Hash>()?;
+        if !self.store.has(hash).await? {
+            let connection = self
+                .router
+                .endpoint()
+                .connect(id.parse::<EndpointId>()?, iroh_blobs::ALPN)
+                .await?;
+            let _temp_tag = self
+                .fetch_blob(connection, HashAndFormat::raw(hash), |_| {})
+                .await?;
+            let owner = BlobOwner::Avatar(id);
+            self.blob_tags.release(&owner).await?;
+            self.blob_tags.tag(hash, owner, None).await?;
+        }
+        Ok(self.store.get_bytes(hash).await?.to_vec())
+    }
+    pub async fn add_blob(
+        &self,
+        data: Vec<u8>,
+        owner: BlobOwner,
+        expires_in_secs: Option<u64>,
+    ) -> Result<String> {
+        self.blob_tags.check_quota(data.len() as u64).await?;
+        let temp_tag = self.store.add_bytes(data).temp_tag().await?;
+        self.blob_tags
+            .tag(temp_tag.hash(), owner, expires_in_secs)
+            .await?;
+        Ok(self.blob_ticket(temp_tag.hash()).to_string())
+    }
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn add_blob_from_path(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        owner: BlobOwner,
+        expires_in_secs: Option<u64>,
+    ) -> Result<String> {
+        let path = std::path::absolute(path)?;
+        self.blob_tags
+            .check_quota(std::fs::metadata(&path)?.len())
+            .await?;
+        let temp_tag = self.store.add_path(path).temp_tag().await?;
+        self.blob_tags
+            .tag(temp_tag.hash(), owner, expires_in_secs)
+            .await?;
+        Ok(self.blob_ticket(temp_tag.hash()).to_string())
+    }
+    fn blob_ticket(&self, hash: Hash) -> BlobTicket {
+        BlobTicket::new(self.router.endpoint().addr(), hash, BlobFormat::Raw)
+    }
+    pub async fn download_blob(
+        &self,
+        ticket: String,
+        owner: BlobOwner,
+        expires_in_secs: Option<u64>,
+        on_progress: impl Fn(u64),
+    ) -> Result<String> {
+        let ticket = ticket.parse::<BlobTicket>()?;
+        let hash = ticket.hash();
+        let mut _temp_tag = None;
+        if !self.store.has(hash).await? {
+            let addr = ticket.addr().clone();
+            let id = addr.id;
+            self.memory_lookup.add_endpoint_info(addr);
+            let connection = self.router.endpoint().connect(id, iroh_blobs::ALPN).await?;
+            _temp_tag = Some(
+                self.fetch_blob(connection, ticket.hash_and_format(), on_progress)
+                    .await?,
+            );
+        }
+        self.blob_tags.tag(hash, owner, expires_in_secs).await?;
+        Ok(hash.to_string())
+    }
+    async fn fetch_blob(
+        &self,
+        connection: Connection,
+        content: HashAndFormat,
+        on_progress: impl Fn(u64),
+    ) -> Result<TempTag> {
+        let temp_tag = self.store.tags().temp_tag(content).await?;
+        let available = self.blob_tags.available().await?;
+        let mut progress = std::pin::pin!(self.store.remote().fetch(connection, content).stream());
+        while let Some(item) = progress.next().await {
+            match item {
+                GetProgressItem::Progress(downloaded) => {
+                    if available.is_some_and(|v| downloaded > v) {
+                        bail!("存储空间不足，超过存储配额");
+                    }
+                    on_progress(downloaded)
                 }
-                "reject" => chat_request.reject()?,
-                _ => (),
-            },
+                GetProgressItem::Done(stats) => on_progress(stats.payload_bytes_read),
+                GetProgressItem::Error(err) => return Err(err.into()),
+            }
         }
-        Ok(().into())
+        Ok(temp_tag)
+    }
+    pub async fn release_blobs(&self, owner: BlobOwner) -> Result<usize> {
+        self.blob_tags.release(&owner).await
     }
-    pub async fn request_person(&self, id: String) -> Result<Person> {
-        Ok(self.person_protocol.request_person(id.parse()?).await?)
+    pub async fn blob_usage(&self) -> Result<BlobUsage> {
+        self.blob_tags.usage().await
     }
-    pub async fn request_friend(&self, id: String) -> Result<bool> {
-        Ok(self.person_protocol.request_friend(id.parse()?).await?)
+    pub async fn collect_garbage(&self) -> Result<GcReport> {
+        self.blob_tags.prune().await
     }
-    pub async fn request_chat(&self, id: String) -> Result<Option<usize>> {
+    pub async fn read_blob(&self, hash: String) -> Result<Vec<u8>> {
+        Ok(self.store.get_bytes(hash.parse::<Hash>()?).await?.to_vec())
+    }
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn export_blob(&self, hash: String, path: impl AsRef<std::path::Path>) -> Result<()> {
+        self.store
+            .export(hash.parse::<Hash>()?, std::path::absolute(path)?)
+            .await?;
+        Ok(())
+    }
+    pub async fn request_person(
+        &self,
+        id: String,
+        tag: Option<ProfileTag>,
+    ) -> Result<Option<Profile>> {
+        self.person_protocol.request_profile(id.parse()?, tag).await
+    }
+    pub async fn request_friend(&self, id: String, message: String) -> Result<Reply<()>> {
         Ok(self
             .person_protocol
-            .request_chat(id.parse()?)
+            .request_friend(id.parse()?, message)
             .await?
-            .map(|v| self.connection_pool.insert(v).get())
-            .transpose()?)
+            .into())
+    }
+    pub async fn request_chat(&self, id: String) -> Result<Reply<usize>> {
+        Ok(
+            match self.person_protocol.request_chat(id.parse()?).await? {
+                Ok(chat) => Reply::Accepted(self.chat_pool.insert(chat).get()?),
+                Err(code) => Reply::Rejected(code),
+            },
+        )
+    }
+    pub async fn send_message(&self, handle: usize, message: ChatMessage) -> Result<()> {
+        let chat = self.chat_pool.get(handle).get()?.clone();
+        chat.send_message(&message).await
+    }
+    pub async fn next_message(&self, handle: usize) -> Result<Option<ChatMessage>> {
+        let chat = self.chat_pool.get(handle).get()?.clone();
+        chat.next_message().await
+    }
+    pub fn send_typing(&self, handle: usize, typing: bool) -> Result<()> {
+        self.chat_pool.get(handle).get()?.send_typing(typing)
+    }
+    pub async fn next_typing(&self, handle: usize) -> Result<Option<bool>> {
+        let chat = self.chat_pool.get(handle).get()?.clone();
+        chat.next_typing().await
+    }
+    pub async fn set_presence(&self, presence: Presence, ids: Vec<String>) -> Result<Vec<String>> {
+        let ids = ids
+            .into_iter()
+            .map(|v| v.parse::<EndpointId>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let results = n0_future::join_all(
+            ids.iter()
+                .map(|id| self.person_protocol.send_presence(*id, presence)),
+        )
+        .await;
+        Ok(ids
+            .into_iter()
+            .zip(results)
+            .filter_map(|(id, result)| match result {
+                Ok(()) => Some(id.to_string()),
+                Err(err) => {
+                    log::debug!("向{}发送在线状态失败：{}", id, err);
+                    None
+                }
+            })
+            .collect())
+    }
+    pub fn chat_stats(&self, handle: usize) -> Result<ConnectionStats> {
+        Ok(self.chat_pool.get(handle).get()?.stats())
+    }
+    pub fn peer_stats(&self, id: String) -> Result<Option<ConnectionStats>> {
+        Ok(self.person_protocol.connection_stats(id.parse()?))
+    }
+    pub fn close_chat(&self, handle: usize) {
+        if let Some(chat) = self.chat_pool.take(handle) {
+            chat.close();
+        }
+    }
+    pub fn block(&self, id: String) -> Result<()> {
+        self.person_protocol.block(id.parse()?);
+        Ok(())
+    }
+    pub fn unblock(&self, id: String) -> Result<bool> {
+        Ok(self.person_protocol.unblock(id.parse()?))
+    }
+    pub fn blocked_list(&self) -> Vec<String> {
+        self.person_protocol
+            .blocked()
+            .into_iter()
+            .map(|v| v.to_string())
+            .collect()
+    }
+    async fn known_addr(&self, id: EndpointId) -> EndpointAddr {
+        let mut addr = EndpointAddr::new(id);
+        if let Some(remote_info) = self.router.endpoint().remote_info(id).await {
+            addr.addrs
+                .extend(remote_info.into_addrs().map(|v| v.into_addr()));
+        }
+        if let Some(endpoint_info) = self.memory_lookup.get_endpoint_info(id) {
+            addr.addrs.extend(endpoint_info.into_endpoint_addr().addrs);
+        }
+        addr
+    }
+    pub async fn generate_ticket(
+        &self,
+        group_id: String,
+        name: String,
+        bootstrap: Vec<String>,
+        expires_in_secs: Option<u64>,
+    ) -> Result<String> {
+        let endpoint = self.router.endpoint();
+        let mut addrs = vec![endpoint.addr()];
+        for id in bootstrap {
+            let id = id.parse::<EndpointId>()?;
+            if id == endpoint.id() {
+                continue;
+            }
+            let addr = self.known_addr(id).await;
+            if addr.is_empty() {
+                bail!("没有{}的地址信息，无法将其作为引导节点", id);
+            }
+            addrs.push(addr);
+        }
+        Ticket::sign(
+            group_id.parse()?,
+            name,
+            addrs,
+            expires_in_secs
+                .map(|v| person_protocol::unix_millis().saturating_add(v.saturating_mul(1000))),
+            endpoint.secret_key(),
+        )?
+        .encode()
     }
     pub async fn subscribe_group(&self, ticket: String) -> Result<usize> {
-        let ticket = serde_json::from_slice::<Ticket>(&BASE64_STANDARD.decode(ticket)?)?;
-        let group = self
+        let ticket = Ticket::decode(&ticket)?.body;
+        let mut bootstrap = Vec::new();
+        for addr in ticket.bootstrap {
+            bootstrap.push(addr.id);
+            self.memory_lookup.add_endpoint_info(addr);
+        }
+        let (sender, receiver) = self
             .gossip_protocol
-            .subscribe(ticket.id, ticket.bootstrap)
+            .subscribe(ticket.id, bootstrap)
             .await?
             .split();
-        Ok(self.group_pool.insert(group).get()?)
+        self.group_pool
+            .insert(Group::new(
+                ticket.id,
+                self.router.endpoint().secret_key().clone(),
+                sender,
+                receiver,
+            ))
+            .get()
+    }
+    pub async fn broadcast(&self, handle: usize, message: ChatMessage) -> Result<()> {
+        let (sender, data) = {
+            let group = self.group_pool.get(handle).get()?;
+            (group.sender(), group.seal(&message)?)
+        };
+        sender.broadcast(data.into()).await?;
+        Ok(())
+    }
+    pub async fn next_group_message(&self, handle: usize) -> Result<Option<GroupMessage>> {
+        let receiver = self.group_pool.get(handle).get()?.message_receiver();
+        Ok(receiver.recv().await.ok())
+    }
+    pub async fn next_member_event(&self, handle: usize) -> Result<Option<MemberEvent>> {
+        let receiver = self.group_pool.get(handle).get()?.member_event_receiver();
+        Ok(receiver.recv().await.ok())
+    }
+    pub fn group_dropped_messages(&self, handle: usize) -> Result<usize> {
+        Ok(self.group_pool.get(handle).get()?.dropped())
+    }
+    pub fn leave_group(&self, handle: usize) {
+        self.group_pool.take(handle);
     }
 }
 
+fn relay_mode(config: &EndpointConfig) -> Result<RelayMode> {
+    let relay_map = match config.relay_mode {
+        RelayModeConfig::DefaultAndCustom => RelayMode::Default.relay_map(),
+        RelayModeConfig::CustomOnly => RelayMap::empty(),
+        RelayModeConfig::Disabled => return Ok(RelayMode::Disabled),
+    };
+    for relay in &config.relays {
+        relay_map.insert(
+            relay.url.parse()?,
+            iroh::RelayConfig {
+                url: relay.url.parse()?,
+                quic: Some(RelayQuicConfig {
+                    port: relay.quic_port,
+                }),
+            }
+            .into(),
+        );
+    }
+    if relay_map.is_empty() {
+        return Ok(RelayMode::Disabled);
+    }
+    Ok(RelayMode::Custom(relay_map))
+}
+fn address_lookup(mut builder: Builder, config: &AddressLookupConfig) -> Result<Builder> {
+    #[cfg(target_family = "wasm")]
+    if config.mdns && !config.pkarr_publish && !config.pkarr_resolve {
+        bail!("当前平台不支持局域网模式");
+    }
+    if config.pkarr_publish {
+        builder = match &config.pkarr_relay {
+            Some(url) => builder.address_lookup(PkarrPublisher::builder(url.parse()?)),
+            None => builder.address_lookup(PkarrPublisher::n0_dns()),
+        };
+    }
+    if config.pkarr_resolve {
+        builder = match &config.pkarr_relay {
+            Some(url) => builder.address_lookup(PkarrResolver::builder(url.parse()?)),
+            None => builder.address_lookup(PkarrResolver::n0_dns()),
+        };
+    }
+    #[cfg(not(target_family = "wasm"))]
+    {
+        use iroh::{
+            address_lookup::{DhtAddressLookup, DnsAddressLookup, MdnsAddressLookup},
+            dns::DnsResolver,
+        };
+
+        if let Some(server) = config.dns_server {
+            builder = builder.dns_resolver(DnsResolver::with_nameserver(server));
+        }
+        if config.dns {
+            builder = match &config.dns_origin {
+                Some(origin) => builder.address_lookup(DnsAddressLookup::builder(origin.clone())),
+                None => builder.address_lookup(DnsAddressLookup::n0_dns()),
+            };
+        }
+        if config.mdns {
+            builder = builder.address_lookup(MdnsAddressLookup::builder());
+        }
+        if config.dht {
+            builder = builder.address_lookup(DhtAddressLookup::builder());
+        }
+    }
+    Ok(builder)
+}
+
 pub fn generate_secret_key() -> Vec<u8> {
     iroh::SecretKey::generate(&mut rand::rng())
         .to_bytes()
@@ -187,15 +649,90 @@ pub fn get_secret_key_id(secret_key: Vec<u8>) -> Result<String> {
             .to_string(),
     )
 }
+pub fn export_mnemonic(secret_key: Vec<u8>) -> Result<String> {
+    let secret_key = SecretKey::from_bytes(secret_key.as_slice().try_into()?);
+    Ok(bip39::Mnemonic::from_entropy(&secret_key.to_bytes())?.to_string())
+}
+pub fn import_mnemonic(mnemonic: String) -> Result<Vec<u8>> {
+    let mnemonic = mnemonic
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    let entropy = bip39::Mnemonic::parse_normalized(&mnemonic)
+        .map_err(|err| eyre!("助记词无效：{}", err))?
+        .to_entropy();
+    if entropy.len() != 32 {
+        bail!("助记词长度不正确，应为24个单词");
+    }
+    Ok(entropy)
+}
+pub fn create_keystore(passphrase: String) -> Result<String> {
+    Keystore::lock(&SecretKey::generate(&mut rand::rng()), &passphrase)?.encode()
+}
+pub fn lock_secret_key(secret_key: Vec<u8>, passphrase: String) -> Result<String> {
+    Keystore::lock(
+        &SecretKey::from_bytes(secret_key.as_slice().try_into()?),
+        &passphrase,
+    )?
+    .encode()
+}
+pub fn unlock_secret_key(keystore: String, passphrase: String) -> Result<Vec<u8>> {
+    Ok(Keystore::decode(&keystore)?
+        .unlock(&passphrase)?
+        .to_bytes()
+        .to_vec())
+}
+pub fn change_passphrase(
+    keystore: String,
+    passphrase: String,
+    new_passphrase: String,
+) -> Result<String> {
+    Keystore::lock(
+        &Keystore::decode(&keystore)?.unlock(&passphrase)?,
+        &new_passphrase,
+    )?
+    .encode()
+}
+pub fn get_keystore_id(keystore: String) -> Result<String> {
+    Ok(Keystore::decode(&keystore)?.id().to_string())
+}
 pub fn generate_group_id() -> String {
     TopicId::from_bytes(rand::random()).to_string()
 }
-pub fn generate_ticket(group_id: String, bootstrap: Vec<String>) -> Result<String> {
-    Ok(BASE64_STANDARD.encode(serde_json::to_vec(&Ticket {
-        id: group_id.parse()?,
-        bootstrap: bootstrap
-            .into_iter()
-            .map(|v| v.parse())
-            .collect::<Result<_, _>>()?,
-    })?))
+pub fn verify_ticket(ticket: String) -> Result<TicketBody> {
+    Ok(Ticket::decode(&ticket)?.body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mnemonic_round_trip() {
+        let mnemonic = export_mnemonic(vec![7; 32]).unwrap();
+        assert_eq!(mnemonic.split_whitespace().count(), 24);
+        let messy = format!("  {}\n", mnemonic.to_uppercase().replace(' ', "   "));
+        assert_eq!(import_mnemonic(messy).unwrap(), vec![7; 32]);
+    }
+
+    #[test]
+    fn mnemonic_rejects_bad_checksum() {
+        let mnemonic = export_mnemonic(vec![7; 32]).unwrap();
+        let mut words = mnemonic.split_whitespace().collect::<Vec<_>>();
+        let last = words.pop().unwrap();
+        words.push(if last == "abandon" {
+            "ability"
+        } else {
+            "abandon"
+        });
+        assert!(import_mnemonic(words.join(" ")).is_err());
+    }
+
+    #[test]
+    fn mnemonic_rejects_twelve_words() {
+        let mnemonic = bip39::Mnemonic::from_entropy(&[7; 16]).unwrap().to_string();
+        assert_eq!(mnemonic.split_whitespace().count(), 12);
+        assert!(import_mnemonic(mnemonic).is_err());
+    }
 }
@@ -0,0 +1,86 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use eyre::{Result, eyre};
+use parking_lot::Mutex;
+
+pub struct PendingEvents<T> {
+    events: Mutex<HashMap<usize, T>>,
+    next_id: AtomicUsize,
+}
+impl<T> Default for PendingEvents<T> {
+    fn default() -> Self {
+        Self {
+            events: Default::default(),
+            next_id: Default::default(),
+        }
+    }
+}
+impl<T> PendingEvents<T> {
+    pub fn next_id(&self) -> usize {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+    pub fn insert(&self, id: usize, event: T, is_expired: impl Fn(&T) -> bool) {
+        let mut events = self.events.lock();
+        events.retain(|_, v| !is_expired(v));
+        events.insert(id, event);
+    }
+    pub fn inspect<R>(&self, id: usize, f: impl FnOnce(&T) -> R) -> Result<R> {
+        Ok(f(self
+            .events
+            .lock()
+            .get(&id)
+            .ok_or_else(|| not_found(id))?))
+    }
+    pub fn take(&self, id: usize) -> Result<T> {
+        self.events.lock().remove(&id).ok_or_else(|| not_found(id))
+    }
+}
+
+fn not_found(id: usize) -> eyre::Report {
+    eyre!("事件{}不存在或已处理", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_monotonic() {
+        let pending = PendingEvents::<()>::default();
+        let ids = (0..100).map(|_| pending.next_id()).collect::<Vec<_>>();
+        assert!(ids.windows(2).all(|v| v[0] < v[1]));
+    }
+
+    #[test]
+    fn take_once() {
+        let pending = PendingEvents::default();
+        let id = pending.next_id();
+        pending.insert(id, "friend", |_| false);
+        assert_eq!(pending.inspect(id, |v| *v).unwrap(), "friend");
+        assert_eq!(pending.take(id).unwrap(), "friend");
+        assert!(pending.take(id).is_err());
+        assert!(pending.inspect(id, |_| ()).is_err());
+    }
+
+    #[test]
+    fn unknown_id() {
+        let pending = PendingEvents::<&str>::default();
+        pending.insert(pending.next_id(), "friend", |_| false);
+        assert!(pending.take(42).is_err());
+        assert!(pending.inspect(42, |_| ()).is_err());
+    }
+
+    #[test]
+    fn drop_expired_on_insert() {
+        let pending = PendingEvents::default();
+        let expired = pending.next_id();
+        pending.insert(expired, true, |v| *v);
+        let fresh = pending.next_id();
+        pending.insert(fresh, false, |v| *v);
+        assert!(pending.take(expired).is_err());
+        assert!(!pending.take(fresh).unwrap());
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use eyre::Result;
+use iroh_blobs::{
+    Hash,
+    api::{Store, Tag},
+    store::{
+        GcConfig,
+        mem::{MemStore, Options},
+    },
+};
+pub use n0_future::boxed::BoxFuture;
+use parking_lot::Mutex;
+
+const BLOB_PREFIX: &str = "blob/";
+const TAG_PREFIX: &str = "tag/";
+
+pub type PersistenceEntries = Vec<(String, Vec<u8>)>;
+
+pub trait BlobPersistence {
+    fn entries(&self) -> BoxFuture<Result<PersistenceEntries>>;
+    fn put(&self, key: String, value: Vec<u8>) -> BoxFuture<Result<()>>;
+    fn remove(&self, key: String) -> BoxFuture<Result<()>>;
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub type SharedBlobPersistence = Arc<dyn BlobPersistence + Send + Sync>;
+#[cfg(target_family = "wasm")]
+pub type SharedBlobPersistence = Arc<dyn BlobPersistence>;
+
+pub enum StoreBackend {
+    Path(PathBuf),
+    Memory,
+    Persistence(SharedBlobPersistence),
+}
+
+#[derive(Default, Clone)]
+pub struct MemoryPersistence {
+    entries: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+}
+impl BlobPersistence for MemoryPersistence {
+    fn entries(&self) -> BoxFuture<Result<PersistenceEntries>> {
+        let entries = self
+            .entries
+            .lock()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        Box::pin(async move { Ok(entries) })
+    }
+    fn put(&self, key: String, value: Vec<u8>) -> BoxFuture<Result<()>> {
+        self.entries.lock().insert(key, value);
+        Box::pin(async { Ok(()) })
+    }
+    fn remove(&self, key: String) -> BoxFuture<Result<()>> {
+        self.entries.lock().remove(&key);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[derive(Clone)]
+pub struct PersistedStore {
+    persistence: SharedBlobPersistence,
+    persisted: Arc<Mutex<HashSet<Hash>>>,
+}
+impl PersistedStore {
+    pub async fn load(
+        persistence: SharedBlobPersistence,
+        gc_config: GcConfig,
+    ) -> Result<(Store, Self)> {
+        let store: Store = MemStore::new_with_opts(Options {
+            gc_config: Some(gc_config),
+        })
+        .into();
+        let mut persisted = HashSet::new();
+        let mut temp_tags = Vec::new();
+        let mut tags = Vec::new();
+        for (key, value) in persistence.entries().await? {
+            if key.starts_with(BLOB_PREFIX) {
+                let temp_tag = store.add_bytes(value).temp_tag().await?;
+                persisted.insert(temp_tag.hash());
+                temp_tags.push(temp_tag);
+            } else if let Some(name) = key.strip_prefix(TAG_PREFIX) {
+                tags.push((
+                    name.to_string(),
+                    Hash::from_bytes(value.as_slice().try_into()?),
+                ));
+            }
+        }
+        for (name, hash) in tags {
+            if persisted.contains(&hash) {
+                store.tags().set(Tag::from(name), hash).await?;
+            } else {
+                log::warn!("持久化存储中缺少标签{}对应的数据", name);
+                persistence
+                    .remove(format!("{}{}", TAG_PREFIX, name))
+                    .await?;
+            }
+        }
+        drop(temp_tags);
+        Ok((
+            store,
+            Self {
+                persistence,
+                persisted: Arc::new(Mutex::new(persisted)),
+            },
+        ))
+    }
+    pub async fn put_blob(&self, store: &Store, hash: Hash) -> Result<()> {
+        if self.persisted.lock().contains(&hash) {
+            return Ok(());
+        }
+        let data = store.get_bytes(hash).await?.to_vec();
+        self.persistence
+            .put(format!("{}{}", BLOB_PREFIX, hash), data)
+            .await?;
+        self.persisted.lock().insert(hash);
+        Ok(())
+    }
+    pub async fn retain_blobs(&self, tagged: &HashSet<Hash>) -> Result<()> {
+        let untagged = self
+            .persisted
+            .lock()
+            .iter()
+            .filter(|v| !tagged.contains(*v))
+            .copied()
+            .collect::<Vec<_>>();
+        for hash in untagged {
+            self.persistence
+                .remove(format!("{}{}", BLOB_PREFIX, hash))
+                .await?;
+            self.persisted.lock().remove(&hash);
+        }
+        Ok(())
+    }
+    pub async fn put_tag(&self, name: &Tag, hash: Hash) -> Result<()> {
+        self.persistence
+            .put(
+                format!("{}{}", TAG_PREFIX, String::from_utf8_lossy(&name.0)),
+                hash.as_bytes().to_vec(),
+            )
+            .await
+    }
+    pub async fn remove_tag(&self, name: &Tag) -> Result<()> {
+        self.persistence
+            .remove(format!(
+                "{}{}",
+                TAG_PREFIX,
+                String::from_utf8_lossy(&name.0)
+            ))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use n0_future::time::Duration;
+
+    use super::*;
+
+    const TAG_NAME: &str = "pupu:test";
+
+    async fn load(persistence: &MemoryPersistence) -> (Store, PersistedStore) {
+        PersistedStore::load(
+            Arc::new(persistence.clone()),
+            GcConfig {
+                interval: Duration::from_secs(60 * 60),
+                add_protected: None,
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn put(store: &Store, persisted: &PersistedStore, data: &[u8]) -> Hash {
+        let temp_tag = store.add_bytes(data.to_vec()).temp_tag().await.unwrap();
+        let name = Tag::from(TAG_NAME);
+        store
+            .tags()
+            .set(name.clone(), temp_tag.hash())
+            .await
+            .unwrap();
+        persisted.put_blob(store, temp_tag.hash()).await.unwrap();
+        persisted.put_tag(&name, temp_tag.hash()).await.unwrap();
+        temp_tag.hash()
+    }
+
+    #[tokio::test]
+    async fn restores_tagged_blobs() {
+        let persistence = MemoryPersistence::default();
+        let (store, persisted) = load(&persistence).await;
+        let hash = put(&store, &persisted, b"hello").await;
+        let (store, _) = load(&persistence).await;
+        assert_eq!(store.get_bytes(hash).await.unwrap().as_ref(), b"hello");
+        assert_eq!(
+            store.tags().get(TAG_NAME).await.unwrap().unwrap().hash,
+            hash
+        );
+    }
+
+    #[tokio::test]
+    async fn removes_released_blobs() {
+        let persistence = MemoryPersistence::default();
+        let (store, persisted) = load(&persistence).await;
+        let hash = put(&store, &persisted, b"hello").await;
+        persisted.remove_tag(&Tag::from(TAG_NAME)).await.unwrap();
+        persisted
+            .retain_blobs(&HashSet::from([hash]))
+            .await
+            .unwrap();
+        assert_eq!(persistence.entries().await.unwrap().len(), 1);
+        persisted.retain_blobs(&HashSet::new()).await.unwrap();
+        assert!(persistence.entries().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn drops_tags_with_missing_blobs() {
+        let persistence = MemoryPersistence::default();
+        persistence
+            .put(
+                format!("{}{}", TAG_PREFIX, TAG_NAME),
+                Hash::new(b"missing").as_bytes().to_vec(),
+            )
+            .await
+            .unwrap();
+        let (store, _) = load(&persistence).await;
+        assert!(store.tags().get(TAG_NAME).await.unwrap().is_none());
+        assert!(persistence.entries().await.unwrap().is_empty());
+    }
+}
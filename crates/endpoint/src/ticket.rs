@@ -0,0 +1,142 @@
+use base64::{Engine, prelude::BASE64_STANDARD};
+use eyre::{Result, bail};
+use iroh::{EndpointAddr, EndpointId, SecretKey, Signature};
+use iroh_gossip::TopicId;
+use person_protocol::unix_millis;
+use serde::{Deserialize, Serialize};
+
+const TICKET_VERSION: u8 = 2;
+const SIGNATURE_CONTEXT: &[u8] = b"pupu/group-ticket";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TicketBody {
+    pub version: u8,
+    pub id: TopicId,
+    pub name: String,
+    pub bootstrap: Vec<EndpointAddr>,
+    pub issuer: EndpointId,
+    pub expires_at: Option<u64>,
+}
+impl TicketBody {
+    fn signing_bytes(&self) -> Result<Vec<u8>> {
+        Ok([SIGNATURE_CONTEXT, &serde_json::to_vec(self)?].concat())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Ticket {
+    #[serde(flatten)]
+    pub body: TicketBody,
+    pub signature: Vec<u8>,
+}
+impl Ticket {
+    pub fn sign(
+        id: TopicId,
+        name: String,
+        bootstrap: Vec<EndpointAddr>,
+        expires_at: Option<u64>,
+        secret_key: &SecretKey,
+    ) -> Result<Self> {
+        let body = TicketBody {
+            version: TICKET_VERSION,
+            id,
+            name,
+            bootstrap,
+            issuer: secret_key.public(),
+            expires_at,
+        };
+        let signature = secret_key.sign(&body.signing_bytes()?).to_bytes().to_vec();
+        Ok(Self { body, signature })
+    }
+    pub fn encode(&self) -> Result<String> {
+        Ok(BASE64_STANDARD.encode(serde_json::to_vec(self)?))
+    }
+    pub fn decode(ticket: &str) -> Result<Self> {
+        let Ok(ticket) = serde_json::from_slice::<Ticket>(&BASE64_STANDARD.decode(ticket)?) else {
+            bail!("无法识别的群组邀请格式");
+        };
+        ticket.verify()?;
+        Ok(ticket)
+    }
+    fn verify(&self) -> Result<()> {
+        if self.body.version != TICKET_VERSION {
+            bail!("不支持的群组邀请版本：{}", self.body.version);
+        }
+        let signature = Signature::from_bytes(self.signature.as_slice().try_into()?);
+        if self
+            .body
+            .issuer
+            .verify(&self.body.signing_bytes()?, &signature)
+            .is_err()
+        {
+            bail!("群组邀请签名验证失败");
+        }
+        if self.body.expires_at.is_some_and(|v| v <= unix_millis()) {
+            bail!("群组邀请已过期");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(expires_at: Option<u64>) -> Ticket {
+        let secret_key = SecretKey::from_bytes(&[1; 32]);
+        Ticket::sign(
+            TopicId::from_bytes([7; 32]),
+            "群组".to_string(),
+            vec![EndpointAddr::new(secret_key.public())],
+            expires_at,
+            &secret_key,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let decoded = Ticket::decode(&ticket(None).encode().unwrap()).unwrap();
+        assert_eq!(decoded.body.id, TopicId::from_bytes([7; 32]));
+        assert_eq!(decoded.body.name, "群组");
+        assert_eq!(
+            decoded.body.issuer,
+            SecretKey::from_bytes(&[1; 32]).public()
+        );
+    }
+
+    #[test]
+    fn rejects_tampering() {
+        let mut ticket = ticket(None);
+        ticket.body.name = "其他群组".to_string();
+        assert!(Ticket::decode(&ticket.encode().unwrap()).is_err());
+    }
+
+    #[test]
+    fn rejects_other_issuer() {
+        let mut ticket = ticket(None);
+        ticket.body.issuer = SecretKey::from_bytes(&[2; 32]).public();
+        assert!(Ticket::decode(&ticket.encode().unwrap()).is_err());
+    }
+
+    #[test]
+    fn rejects_expired() {
+        let expired = ticket(Some(unix_millis() - 1));
+        assert!(Ticket::decode(&expired.encode().unwrap()).is_err());
+        let valid = ticket(Some(unix_millis() + 60_000));
+        assert!(Ticket::decode(&valid.encode().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let mut ticket = ticket(None);
+        ticket.body.version = 1;
+        assert!(Ticket::decode(&ticket.encode().unwrap()).is_err());
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(Ticket::decode("不是邀请").is_err());
+        assert!(Ticket::decode(&BASE64_STANDARD.encode(b"{}")).is_err());
+    }
+}
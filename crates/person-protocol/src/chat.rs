@@ -0,0 +1,99 @@
+use eyre::{Result, ensure};
+use iroh::{
+    EndpointId,
+    endpoint::{Connection, ConnectionError},
+};
+use rkyv::{Archive, util::AlignedVec};
+
+use crate::diagnostics::{ConnectionStats, PathTracker};
+
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+#[derive(
+    Archive, rkyv::Serialize, rkyv::Deserialize, Debug, Clone, serde::Serialize, serde::Deserialize,
+)]
+pub struct ChatMessage {
+    pub content: String,
+}
+
+#[derive(Archive, rkyv::Serialize, rkyv::Deserialize)]
+enum Signal {
+    Typing(bool),
+}
+
+#[derive(Debug, Clone)]
+pub struct Chat {
+    connection: Connection,
+    path_tracker: PathTracker,
+}
+impl Chat {
+    pub(crate) fn new(connection: Connection) -> Self {
+        Self {
+            path_tracker: PathTracker::new(&connection),
+            connection,
+        }
+    }
+    pub fn remote_id(&self) -> EndpointId {
+        self.connection.remote_id()
+    }
+    pub async fn send_message(&self, message: &ChatMessage) -> Result<()> {
+        let data = rkyv::to_bytes::<rkyv::rancor::Error>(message)?;
+        ensure!(
+            data.len() <= MAX_MESSAGE_SIZE,
+            "消息超过大小限制（{}字节）",
+            MAX_MESSAGE_SIZE
+        );
+        let mut send = self.connection.open_uni().await?;
+        send.write_all(&data).await?;
+        send.finish()?;
+        Ok(())
+    }
+    pub async fn next_message(&self) -> Result<Option<ChatMessage>> {
+        let mut recv = match self.connection.accept_uni().await {
+            Ok(recv) => recv,
+            Err(err) if is_closed(&err) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let mut data = AlignedVec::<16>::new();
+        data.extend_from_slice(&recv.read_to_end(MAX_MESSAGE_SIZE).await?);
+        Ok(Some(rkyv::from_bytes::<ChatMessage, rkyv::rancor::Error>(
+            &data,
+        )?))
+    }
+    pub fn send_typing(&self, typing: bool) -> Result<()> {
+        self.connection.send_datagram(
+            rkyv::to_bytes::<rkyv::rancor::Error>(&Signal::Typing(typing))?
+                .to_vec()
+                .into(),
+        )?;
+        Ok(())
+    }
+    pub async fn next_typing(&self) -> Result<Option<bool>> {
+        loop {
+            let datagram = match self.connection.read_datagram().await {
+                Ok(datagram) => datagram,
+                Err(err) if is_closed(&err) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+            let mut data = AlignedVec::<16>::new();
+            data.extend_from_slice(&datagram);
+            match rkyv::from_bytes::<Signal, rkyv::rancor::Error>(&data) {
+                Ok(Signal::Typing(typing)) => return Ok(Some(typing)),
+                Err(_) => log::debug!("忽略无法识别的聊天信号"),
+            }
+        }
+    }
+    pub fn stats(&self) -> ConnectionStats {
+        self.path_tracker.stats(&self.connection)
+    }
+    pub fn close(&self) {
+        self.connection.close(0u32.into(), b"chat closed");
+    }
+}
+
+fn is_closed(err: &ConnectionError) -> bool {
+    matches!(
+        err,
+        ConnectionError::ApplicationClosed(_) | ConnectionError::LocallyClosed
+    )
+}
@@ -0,0 +1,129 @@
+use rkyv::Archive;
+use strum::Display;
+
+#[derive(
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Code {
+    Rejected,
+    Busy,
+    Blocked,
+    NotAccepting,
+    RateLimited,
+    Expired,
+    InvalidRequest,
+    TooLarge,
+    Internal,
+}
+impl Code {
+    pub fn close_code(self) -> u32 {
+        match self {
+            Code::Rejected => 1,
+            Code::Busy => 2,
+            Code::Blocked => 3,
+            Code::NotAccepting => 4,
+            Code::RateLimited => 5,
+            Code::Expired => 6,
+            Code::InvalidRequest => 7,
+            Code::TooLarge => 8,
+            Code::Internal => 9,
+        }
+    }
+    pub fn from_close_code(code: u64) -> Option<Self> {
+        Some(match code {
+            1 => Code::Rejected,
+            2 => Code::Busy,
+            3 => Code::Blocked,
+            4 => Code::NotAccepting,
+            5 => Code::RateLimited,
+            6 => Code::Expired,
+            7 => Code::InvalidRequest,
+            8 => Code::TooLarge,
+            9 => Code::Internal,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum Decision {
+    Accepted,
+    Rejected(Code),
+}
+impl From<Decision> for Result<(), Code> {
+    fn from(value: Decision) -> Self {
+        match value {
+            Decision::Accepted => Ok(()),
+            Decision::Rejected(code) => Err(code),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RemoteError(pub Code);
+impl std::fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "对方返回错误：{}", self.0)
+    }
+}
+impl std::error::Error for RemoteError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODES: [Code; 9] = [
+        Code::Rejected,
+        Code::Busy,
+        Code::Blocked,
+        Code::NotAccepting,
+        Code::RateLimited,
+        Code::Expired,
+        Code::InvalidRequest,
+        Code::TooLarge,
+        Code::Internal,
+    ];
+
+    #[test]
+    fn close_code_round_trip() {
+        for code in CODES {
+            assert_eq!(Code::from_close_code(code.close_code().into()), Some(code));
+        }
+    }
+
+    #[test]
+    fn close_codes_are_unique() {
+        for (i, a) in CODES.iter().enumerate() {
+            for b in &CODES[i + 1..] {
+                assert_ne!(a.close_code(), b.close_code());
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_close_code() {
+        assert_eq!(Code::from_close_code(0), None);
+        assert_eq!(Code::from_close_code(u64::MAX), None);
+    }
+
+    #[test]
+    fn decision_into_result() {
+        assert_eq!(Result::<(), Code>::from(Decision::Accepted), Ok(()));
+        assert_eq!(
+            Result::<(), Code>::from(Decision::Rejected(Code::Expired)),
+            Err(Code::Expired)
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use n0_future::time::Duration;
+
+#[derive(Debug, Clone, Copy, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    Wait,
+    #[default]
+    RejectNew,
+    DropOldest,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub friend_request_timeout_secs: u64,
+    pub chat_request_timeout_secs: u64,
+    pub event_queue_capacity: usize,
+    pub overflow_policy: OverflowPolicy,
+    pub accept_friend_requests: bool,
+    pub accept_chat_requests: bool,
+    pub max_request_size: usize,
+    pub max_response_size: usize,
+    pub rate_limit_burst: u32,
+    pub rate_limit_per_second: u32,
+    pub rate_limit_ban_secs: u64,
+    pub connection_idle_secs: u64,
+}
+impl Config {
+    pub fn friend_request_timeout(&self) -> Duration {
+        Duration::from_secs(self.friend_request_timeout_secs)
+    }
+    pub fn chat_request_timeout(&self) -> Duration {
+        Duration::from_secs(self.chat_request_timeout_secs)
+    }
+    pub fn rate_limit_ban_duration(&self) -> Duration {
+        Duration::from_secs(self.rate_limit_ban_secs)
+    }
+    pub fn connection_idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.connection_idle_secs.max(1))
+    }
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            friend_request_timeout_secs: 24 * 60 * 60,
+            chat_request_timeout_secs: 60,
+            event_queue_capacity: 10,
+            overflow_policy: OverflowPolicy::default(),
+            accept_friend_requests: true,
+            accept_chat_requests: true,
+            max_request_size: 64 * 1024,
+            max_response_size: 1024 * 1024,
+            rate_limit_burst: 20,
+            rate_limit_per_second: 2,
+            rate_limit_ban_secs: 5 * 60,
+            connection_idle_secs: 60,
+        }
+    }
+}
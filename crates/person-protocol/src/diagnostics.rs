@@ -0,0 +1,93 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use iroh::{TransportAddr, Watcher, endpoint::Connection};
+use n0_future::{StreamExt, task::AbortOnDropHandle};
+use parking_lot::Mutex;
+
+use crate::unix_millis;
+
+const MAX_PATH_CHANGES: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "type", content = "addr", rename_all = "snake_case")]
+pub enum PathKind {
+    Direct(String),
+    Relay(String),
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PathChange {
+    pub at: u64,
+    pub path: Option<PathKind>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConnectionStats {
+    pub remote_id: String,
+    pub path: Option<PathKind>,
+    pub rtt_ms: Option<u64>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub path_changes: Vec<PathChange>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PathTracker {
+    history: Arc<Mutex<VecDeque<PathChange>>>,
+    _task: Arc<AbortOnDropHandle<()>>,
+}
+impl PathTracker {
+    pub fn new(connection: &Connection) -> Self {
+        let history = Arc::new(Mutex::new(VecDeque::new()));
+        let task = n0_future::task::spawn({
+            let history = history.clone();
+            let mut paths = connection.paths().stream();
+            async move {
+                let mut current = None;
+                while let Some(paths) = paths.next().await {
+                    let path = paths
+                        .iter()
+                        .find(|v| v.is_selected())
+                        .map(|v| path_kind(v.remote_addr()));
+                    if path == current {
+                        continue;
+                    }
+                    current = path.clone();
+                    let mut history = history.lock();
+                    if history.len() >= MAX_PATH_CHANGES {
+                        history.pop_front();
+                    }
+                    history.push_back(PathChange {
+                        at: unix_millis(),
+                        path,
+                    });
+                }
+            }
+        });
+        Self {
+            history,
+            _task: Arc::new(AbortOnDropHandle::new(task)),
+        }
+    }
+    pub fn stats(&self, connection: &Connection) -> ConnectionStats {
+        let paths = connection.paths().get();
+        let selected = paths.iter().find(|v| v.is_selected());
+        let stats = connection.stats();
+        ConnectionStats {
+            remote_id: connection.remote_id().to_string(),
+            path: selected.map(|v| path_kind(v.remote_addr())),
+            rtt_ms: selected.map(|v| v.rtt().as_millis() as u64),
+            bytes_sent: stats.udp_tx.bytes,
+            bytes_received: stats.udp_rx.bytes,
+            path_changes: self.history.lock().iter().cloned().collect(),
+        }
+    }
+}
+
+fn path_kind(addr: &TransportAddr) -> PathKind {
+    match addr {
+        TransportAddr::Relay(url) => PathKind::Relay(url.to_string()),
+        TransportAddr::Ip(addr) => PathKind::Direct(addr.to_string()),
+        addr => PathKind::Direct(format!("{:?}", addr)),
+    }
+}
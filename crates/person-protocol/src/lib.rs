@@ -1,29 +1,69 @@
-use std::sync::Arc;
+mod chat;
+mod code;
+mod config;
+mod diagnostics;
+mod pool;
+mod presence;
+mod profile;
+mod rate_limit;
+mod v1;
+mod version;
+mod wire;
+
+use std::{collections::HashSet, sync::Arc};
 
 use eyre::{Result, bail, eyre};
 use futures::channel::oneshot;
 use iroh::{
     Endpoint, EndpointId,
-    endpoint::Connection,
+    endpoint::{
+        ConnectError, ConnectingError, Connection, ConnectionError, RecvStream, SendStream,
+        TransportErrorCode,
+    },
     protocol::{AcceptError, ProtocolHandler},
 };
+use n0_future::time::Duration;
+use parking_lot::RwLock;
 use rkyv::Archive;
 use strum::Display;
 
-pub const ALPN: &[u8] = b"person/v1";
+pub use crate::{
+    chat::{Chat, ChatMessage},
+    code::{Code, RemoteError},
+    config::{Config, OverflowPolicy},
+    diagnostics::{ConnectionStats, PathChange, PathKind},
+    presence::{Presence, PresenceUpdate},
+    profile::{Profile, ProfileTag, unix_millis},
+    v1::ALPN as ALPN_V1,
+    version::{ALPN as ALPN_V2, Capabilities, Feature, UnsupportedVersion},
+};
+use crate::{
+    code::Decision,
+    pool::ConnectionPool,
+    presence::PresenceQueue,
+    rate_limit::RateLimiter,
+    version::{Hello, HelloResponse},
+    wire::MessageTooLarge,
+};
 
-#[derive(Archive, rkyv::Serialize, rkyv::Deserialize)]
+const NO_APPLICATION_PROTOCOL: u8 = 120;
+
+#[derive(Archive, rkyv::Serialize, rkyv::Deserialize, Clone)]
 enum Request {
-    Person,
-    Friend,
+    Profile(Option<ProfileTag>),
+    Friend(Option<FriendGreeting>),
     Chat,
+    Presence(Presence),
 }
 
 #[derive(Archive, rkyv::Serialize, rkyv::Deserialize)]
 enum Response {
-    Person(Person),
-    Friend(bool),
-    Chat(bool),
+    Profile(Profile),
+    ProfileNotModified,
+    Friend(Decision),
+    Chat(Decision),
+    Ack,
+    Error(Code),
 }
 
 #[derive(
@@ -31,34 +71,53 @@ enum Response {
 )]
 pub struct Person {
     pub name: String,
-    pub avatar: Option<Vec<u8>>,
+    pub avatar: Option<String>,
     pub bio: String,
 }
 
+pub const MAX_GREETING_LENGTH: usize = 200;
+
+#[derive(
+    Archive, rkyv::Serialize, rkyv::Deserialize, Debug, Clone, serde::Serialize, serde::Deserialize,
+)]
+pub struct FriendGreeting {
+    pub message: String,
+    pub profile: Profile,
+    pub timestamp: u64,
+}
+
 #[derive(Display)]
 pub enum Event {
     FriendRequest(FriendRequest),
     ChatRequest(ChatRequest),
+    PresenceUpdate(PresenceUpdate),
 }
 
 pub struct FriendRequest {
     response_sender: oneshot::Sender<bool>,
     remote_id: EndpointId,
+    greeting: Option<FriendGreeting>,
 }
 impl FriendRequest {
     pub fn remote_id(&self) -> EndpointId {
         self.remote_id
     }
+    pub fn greeting(&self) -> Option<&FriendGreeting> {
+        self.greeting.as_ref()
+    }
+    pub fn is_expired(&self) -> bool {
+        self.response_sender.is_canceled()
+    }
     pub fn accept(self) -> Result<()> {
         self.response_sender
             .send(true)
-            .map_err(|_| eyre!("发送同意好友请求消息失败"))?;
+            .map_err(|_| eyre!("好友请求已过期"))?;
         Ok(())
     }
     pub fn reject(self) -> Result<()> {
         self.response_sender
             .send(false)
-            .map_err(|_| eyre!("发送拒绝好友请求消息失败"))?;
+            .map_err(|_| eyre!("好友请求已过期"))?;
         Ok(())
     }
 }
@@ -71,16 +130,19 @@ impl ChatRequest {
     pub fn remote_id(&self) -> EndpointId {
         self.connection.remote_id()
     }
-    pub fn accept(self) -> Result<Connection> {
+    pub fn is_expired(&self) -> bool {
+        self.response_sender.is_canceled()
+    }
+    pub fn accept(self) -> Result<Chat> {
         self.response_sender
             .send(true)
-            .map_err(|_| eyre!("发送同意聊天请求消息失败"))?;
-        Ok(self.connection)
+            .map_err(|_| eyre!("聊天请求已过期"))?;
+        Ok(Chat::new(self.connection))
     }
     pub fn reject(self) -> Result<()> {
         self.response_sender
             .send(false)
-            .map_err(|_| eyre!("发送拒绝聊天请求消息失败"))?;
+            .map_err(|_| eyre!("聊天请求已过期"))?;
         Ok(())
     }
 }
@@ -88,124 +150,733 @@ impl ChatRequest {
 #[derive(Debug, Clone)]
 pub struct PersonProtocol {
     endpoint: Endpoint,
-    person: Arc<Person>,
+    profile: Arc<RwLock<Profile>>,
+    config: Arc<Config>,
+    rate_limiter: Arc<RateLimiter>,
+    connection_pool: Arc<ConnectionPool>,
+    blocked: Arc<RwLock<HashSet<EndpointId>>>,
     event_sender: async_channel::Sender<Event>,
     event_receiver: async_channel::Receiver<Event>,
+    presence_queue: Arc<PresenceQueue>,
 }
 impl PersonProtocol {
-    pub fn new(endpoint: Endpoint, person: Person) -> Self {
-        let (event_sender, event_receiver) = async_channel::bounded(10);
-        Self {
+    pub fn new(endpoint: Endpoint, person: Person, config: Config) -> Result<Self> {
+        let (event_sender, event_receiver) =
+            async_channel::bounded(config.event_queue_capacity.max(1));
+        let profile = Profile::sign(person, endpoint.secret_key())?;
+        Ok(Self {
             endpoint,
-            person: Arc::new(person),
+            profile: Arc::new(RwLock::new(profile)),
+            rate_limiter: Arc::new(RateLimiter::new(&config)),
+            connection_pool: ConnectionPool::new(config.connection_idle_timeout()),
+            config: Arc::new(config),
+            blocked: Default::default(),
             event_sender,
             event_receiver,
+            presence_queue: Arc::new(PresenceQueue::new()),
+        })
+    }
+    pub fn profile(&self) -> Profile {
+        self.profile.read().clone()
+    }
+    pub fn update_person(&self, person: Person) -> Result<()> {
+        *self.profile.write() = Profile::sign(person, self.endpoint.secret_key())?;
+        Ok(())
+    }
+    pub fn block(&self, id: EndpointId) {
+        self.blocked.write().insert(id);
+    }
+    pub fn unblock(&self, id: EndpointId) -> bool {
+        self.blocked.write().remove(&id)
+    }
+    pub fn is_blocked(&self, id: EndpointId) -> bool {
+        self.blocked.read().contains(&id)
+    }
+    pub fn blocked(&self) -> Vec<EndpointId> {
+        self.blocked.read().iter().copied().collect()
+    }
+    fn report_error(&self, remote_id: EndpointId, err: &eyre::Report) {
+        if err.is::<MessageTooLarge>() {
+            log::warn!("{}发送的请求超过大小限制，已暂时拒绝", remote_id);
+            self.rate_limiter.penalize(remote_id);
+        } else {
+            log::warn!("处理{}的请求失败：{}", remote_id, err);
         }
     }
     async fn handle_connection(&self, connection: Connection) -> Result<()> {
+        match connection.alpn() {
+            ALPN_V1 => self.handle_v1_connection(connection).await,
+            ALPN_V2 => self.handle_v2_connection(connection).await,
+            _ => bail!("未知的协议版本"),
+        }
+    }
+    async fn handle_v1_connection(&self, connection: Connection) -> Result<()> {
         if let Ok((mut send, mut recv)) = connection.accept_bi().await {
-            if let Ok(data) = recv.read_to_end(usize::MAX).await {
-                match rkyv::from_bytes::<Request, rkyv::rancor::Error>(&data)? {
-                    Request::Person => {
-                        send.write_all(&rkyv::to_bytes::<rkyv::rancor::Error>(&Response::Person(
-                            (*self.person).clone(),
-                        ))?)
-                        .await?;
-                        send.finish()?;
-                        connection.closed().await;
-                    }
-                    Request::Friend => {
-                        let (sender, receiver) = oneshot::channel::<bool>();
-                        self.event_sender
-                            .send(Event::FriendRequest(FriendRequest {
-                                remote_id: connection.remote_id(),
-                                response_sender: sender,
-                            }))
-                            .await?;
-                        let result = receiver.await?;
-                        send.write_all(&rkyv::to_bytes::<rkyv::rancor::Error>(&Response::Friend(
-                            result,
-                        ))?)
-                        .await?;
-                        send.finish()?;
-                        connection.closed().await;
+            let request =
+                wire::read::<v1::Request>(&mut recv, self.config.max_request_size).await?;
+            let is_chat = matches!(request, v1::Request::Chat);
+            let response = self.handle_request(&connection, request.into()).await?;
+            wire::write(&mut send, &v1::Response::try_from(response)?).await?;
+            if !is_chat {
+                connection.closed().await;
+            }
+        }
+        Ok(())
+    }
+    async fn handle_v2_connection(&self, connection: Connection) -> Result<()> {
+        let (mut send, mut recv) = connection.accept_bi().await?;
+        let hello_response = wire::read::<Hello>(&mut recv, self.config.max_request_size)
+            .await?
+            .negotiate();
+        let is_unsupported = matches!(hello_response, HelloResponse::Unsupported { .. });
+        wire::write(&mut send, &hello_response).await?;
+        if is_unsupported {
+            connection.closed().await;
+            return Ok(());
+        }
+        while let Ok((send, recv)) = connection.accept_bi().await {
+            let remote_id = connection.remote_id();
+            if self.is_blocked(remote_id) {
+                log::info!("已断开与被屏蔽的{}的连接", remote_id);
+                connection.close(Code::Blocked.close_code().into(), b"blocked");
+                break;
+            }
+            if !self.rate_limiter.check(remote_id) {
+                log::warn!("{}请求过于频繁，已暂时拒绝", remote_id);
+                connection.close(Code::RateLimited.close_code().into(), b"rate limited");
+                break;
+            }
+            let this = self.clone();
+            let connection = connection.clone();
+            n0_future::task::spawn(async move {
+                if let Err(err) = this.handle_stream(&connection, send, recv).await {
+                    this.report_error(remote_id, &err);
+                }
+            });
+        }
+        Ok(())
+    }
+    async fn handle_stream(
+        &self,
+        connection: &Connection,
+        mut send: SendStream,
+        mut recv: RecvStream,
+    ) -> Result<()> {
+        let request = match wire::read::<Request>(&mut recv, self.config.max_request_size).await {
+            Ok(request) => request,
+            Err(err) => {
+                let code = if err.is::<MessageTooLarge>() {
+                    Code::TooLarge
+                } else {
+                    Code::InvalidRequest
+                };
+                wire::write(&mut send, &Response::Error(code)).await?;
+                return Err(err);
+            }
+        };
+        match self.handle_request(connection, request).await {
+            Ok(response) => wire::write(&mut send, &response).await,
+            Err(err) => {
+                wire::write(&mut send, &Response::Error(Code::Internal)).await?;
+                Err(err)
+            }
+        }
+    }
+    async fn handle_request(&self, connection: &Connection, request: Request) -> Result<Response> {
+        match request {
+            Request::Profile(tag) => {
+                let profile = self.profile();
+                if tag == Some(profile.tag) {
+                    return Ok(Response::ProfileNotModified);
+                }
+                Ok(Response::Profile(profile))
+            }
+            Request::Friend(greeting) => {
+                let remote_id = connection.remote_id();
+                if let Some(greeting) = &greeting
+                    && (greeting.message.chars().count() > MAX_GREETING_LENGTH
+                        || greeting.profile.verify(remote_id).is_err())
+                {
+                    log::warn!("拒绝来自{}的无效好友请求", remote_id);
+                    return Ok(Response::Friend(Decision::Rejected(Code::InvalidRequest)));
+                }
+                if !self.config.accept_friend_requests {
+                    return Ok(Response::Friend(Decision::Rejected(Code::NotAccepting)));
+                }
+                let (sender, receiver) = oneshot::channel::<bool>();
+                let event = Event::FriendRequest(FriendRequest {
+                    remote_id,
+                    response_sender: sender,
+                    greeting,
+                });
+                if !self
+                    .push_event(event, self.config.friend_request_timeout())
+                    .await?
+                {
+                    return Ok(Response::Friend(Decision::Rejected(Code::Busy)));
+                }
+                Ok(Response::Friend(
+                    wait_decision(receiver, self.config.friend_request_timeout()).await,
+                ))
+            }
+            Request::Chat => {
+                if !self.config.accept_chat_requests {
+                    return Ok(Response::Chat(Decision::Rejected(Code::NotAccepting)));
+                }
+                let (sender, receiver) = oneshot::channel::<bool>();
+                let event = Event::ChatRequest(ChatRequest {
+                    response_sender: sender,
+                    connection: connection.clone(),
+                });
+                if !self
+                    .push_event(event, self.config.chat_request_timeout())
+                    .await?
+                {
+                    return Ok(Response::Chat(Decision::Rejected(Code::Busy)));
+                }
+                Ok(Response::Chat(
+                    wait_decision(receiver, self.config.chat_request_timeout()).await,
+                ))
+            }
+            Request::Presence(presence) => {
+                self.presence_queue
+                    .push(PresenceUpdate::new(connection.remote_id(), presence));
+                Ok(Response::Ack)
+            }
+        }
+    }
+    async fn push_event(&self, mut event: Event, timeout: Duration) -> Result<bool> {
+        match self.config.overflow_policy {
+            OverflowPolicy::Wait => {
+                match n0_future::time::timeout(timeout, self.event_sender.send(event)).await {
+                    Ok(result) => {
+                        result?;
+                        Ok(true)
                     }
-                    Request::Chat => {
-                        let (sender, receiver) = oneshot::channel::<bool>();
-                        self.event_sender
-                            .send(Event::ChatRequest(ChatRequest {
-                                response_sender: sender,
-                                connection,
-                            }))
-                            .await?;
-                        let result = receiver.await?;
-                        send.write_all(&rkyv::to_bytes::<rkyv::rancor::Error>(&Response::Chat(
-                            result,
-                        ))?)
-                        .await?;
-                        send.finish()?;
+                    Err(_) => {
+                        log::warn!("事件队列已满且等待超时，已拒绝新的请求");
+                        Ok(false)
                     }
                 }
             }
+            OverflowPolicy::RejectNew => match self.event_sender.try_send(event) {
+                Ok(()) => Ok(true),
+                Err(async_channel::TrySendError::Full(event)) => {
+                    log::warn!("事件队列已满，已拒绝新的{}", event);
+                    Ok(false)
+                }
+                Err(async_channel::TrySendError::Closed(_)) => bail!("事件队列已关闭"),
+            },
+            OverflowPolicy::DropOldest => loop {
+                match self.event_sender.try_send(event) {
+                    Ok(()) => return Ok(true),
+                    Err(async_channel::TrySendError::Full(v)) => {
+                        event = v;
+                        if let Ok(oldest) = self.event_receiver.try_recv() {
+                            log::warn!("事件队列已满，已丢弃最早的{}", oldest);
+                        }
+                    }
+                    Err(async_channel::TrySendError::Closed(_)) => bail!("事件队列已关闭"),
+                }
+            },
         }
-        Ok(())
     }
     pub async fn next_event(&self) -> Result<Event> {
-        Ok(self.event_receiver.recv().await?)
+        loop {
+            if let Ok(event) = self.event_receiver.try_recv() {
+                return Ok(event);
+            }
+            if let Some(update) = self.presence_queue.take() {
+                return Ok(Event::PresenceUpdate(update));
+            }
+            let event =
+                n0_future::future::or(async { Some(self.event_receiver.recv().await) }, async {
+                    self.presence_queue.notified().await;
+                    None
+                })
+                .await;
+            if let Some(event) = event {
+                return Ok(event?);
+            }
+        }
     }
-    pub async fn request_person(&self, id: EndpointId) -> Result<Person> {
-        let connection = self.endpoint.connect(id, ALPN).await?;
-        let (mut send, mut recv) = connection.open_bi().await?;
-        send.write_all(&rkyv::to_bytes::<rkyv::rancor::Error>(&Request::Person)?)
-            .await?;
-        send.finish()?;
-        let Response::Person(person) = rkyv::from_bytes::<Response, rkyv::rancor::Error>(
-            &recv.read_to_end(usize::MAX).await?,
-        )?
-        else {
-            bail!("响应数据非预期");
-        };
-        Ok(person)
+    async fn connect(&self, id: EndpointId) -> Result<Connection> {
+        Ok(match self.endpoint.connect(id, ALPN_V2).await {
+            Err(err) if is_alpn_mismatch(&err) => self.endpoint.connect(id, ALPN_V1).await?,
+            connection => connection?,
+        })
     }
-    pub async fn request_friend(&self, id: EndpointId) -> Result<bool> {
-        let connection = self.endpoint.connect(id, ALPN).await?;
-        let (mut send, mut recv) = connection.open_bi().await?;
-        send.write_all(&rkyv::to_bytes::<rkyv::rancor::Error>(&Request::Friend)?)
-            .await?;
-        send.finish()?;
-        let Response::Friend(result) = rkyv::from_bytes::<Response, rkyv::rancor::Error>(
-            &recv.read_to_end(usize::MAX).await?,
-        )?
-        else {
-            bail!("响应数据非预期");
-        };
-        Ok(result)
+    async fn handshake(&self, connection: &Connection) -> Result<Capabilities> {
+        match connection.alpn() {
+            ALPN_V2 => {
+                let (mut send, mut recv) = connection.open_bi().await?;
+                wire::write(&mut send, &Hello::local()).await?;
+                Ok(
+                    wire::read::<HelloResponse>(&mut recv, self.config.max_response_size)
+                        .await?
+                        .capabilities()?,
+                )
+            }
+            ALPN_V1 => Ok(Capabilities::v1()),
+            _ => bail!("未知的协议版本"),
+        }
     }
-    pub async fn request_chat(&self, id: EndpointId) -> Result<Option<Connection>> {
-        let connection = self.endpoint.connect(id, ALPN).await?;
+    async fn exchange(
+        &self,
+        connection: &Connection,
+        capabilities: &Capabilities,
+        request: Request,
+    ) -> Result<Response> {
         let (mut send, mut recv) = connection.open_bi().await?;
-        send.write_all(&rkyv::to_bytes::<rkyv::rancor::Error>(&Request::Chat)?)
-            .await?;
-        send.finish()?;
-        let Response::Chat(result) = rkyv::from_bytes::<Response, rkyv::rancor::Error>(
-            &recv.read_to_end(usize::MAX).await?,
-        )?
-        else {
-            bail!("响应数据非预期");
+        if capabilities.version == 1 {
+            wire::write(&mut send, &v1::Request::try_from(request)?).await?;
+            wire::read::<v1::Response>(&mut recv, self.config.max_response_size)
+                .await?
+                .try_into()
+        } else {
+            wire::write(&mut send, &request).await?;
+            wire::read::<Response>(&mut recv, self.config.max_response_size).await
+        }
+    }
+    async fn request(&self, id: EndpointId, request: Request) -> Result<Response> {
+        if let Some((connection, capabilities)) = self.connection_pool.get(id) {
+            match self
+                .exchange(&connection, &capabilities, request.clone())
+                .await
+            {
+                Ok(response) => return Ok(response),
+                Err(err) => {
+                    if connection.close_reason().is_none() {
+                        return Err(err);
+                    }
+                    self.connection_pool.remove(id, &connection);
+                    if let Some(code) = close_code(&connection) {
+                        return Ok(Response::Error(code));
+                    }
+                    log::debug!("与{}的连接已断开，重新连接", id);
+                }
+            }
+        }
+        Ok(self.request_with_new_connection(id, request, true).await?.1)
+    }
+    async fn request_with_new_connection(
+        &self,
+        id: EndpointId,
+        request: Request,
+        reuse: bool,
+    ) -> Result<(Connection, Response)> {
+        let connection = self.connect(id).await?;
+        let result = async {
+            let capabilities = self.handshake(&connection).await?;
+            if reuse && capabilities.version > 1 {
+                self.connection_pool
+                    .insert(id, connection.clone(), capabilities.clone());
+            }
+            self.exchange(&connection, &capabilities, request).await
+        }
+        .await;
+        match result {
+            Ok(response) => Ok((connection, response)),
+            Err(err) => match close_code(&connection) {
+                Some(code) => Ok((connection, Response::Error(code))),
+                None => Err(err),
+            },
+        }
+    }
+    pub async fn capabilities(&self, id: EndpointId) -> Result<Capabilities> {
+        if let Some((_, capabilities)) = self.connection_pool.get(id) {
+            return Ok(capabilities);
+        }
+        let connection = self.connect(id).await?;
+        let capabilities = self.handshake(&connection).await?;
+        if capabilities.version > 1 {
+            self.connection_pool
+                .insert(id, connection, capabilities.clone());
+        }
+        Ok(capabilities)
+    }
+    pub fn connection_stats(&self, id: EndpointId) -> Option<ConnectionStats> {
+        self.connection_pool.stats(id)
+    }
+    pub async fn request_profile(
+        &self,
+        id: EndpointId,
+        tag: Option<ProfileTag>,
+    ) -> Result<Option<Profile>> {
+        match self.request(id, Request::Profile(tag)).await? {
+            Response::Profile(profile) => {
+                profile.verify(id)?;
+                Ok(Some(profile))
+            }
+            Response::ProfileNotModified => Ok(None),
+            Response::Error(code) => Err(RemoteError(code).into()),
+            _ => bail!("响应数据非预期"),
+        }
+    }
+    pub async fn request_friend(
+        &self,
+        id: EndpointId,
+        message: String,
+    ) -> Result<Result<(), Code>> {
+        if message.chars().count() > MAX_GREETING_LENGTH {
+            bail!("好友请求附言不能超过{}个字符", MAX_GREETING_LENGTH);
+        }
+        let greeting = FriendGreeting {
+            message,
+            profile: self.profile(),
+            timestamp: unix_millis(),
         };
-        if !result {
-            return Ok(None);
+        match self.request(id, Request::Friend(Some(greeting))).await? {
+            Response::Friend(decision) => Ok(decision.into()),
+            Response::Error(code) => Ok(Err(code)),
+            _ => bail!("响应数据非预期"),
+        }
+    }
+    pub async fn request_chat(&self, id: EndpointId) -> Result<Result<Chat, Code>> {
+        match self
+            .request_with_new_connection(id, Request::Chat, false)
+            .await?
+        {
+            (connection, Response::Chat(decision)) => {
+                Ok(Result::<(), Code>::from(decision).map(|()| Chat::new(connection)))
+            }
+            (_, Response::Error(code)) => Ok(Err(code)),
+            _ => bail!("响应数据非预期"),
+        }
+    }
+    pub async fn send_presence(&self, id: EndpointId, presence: Presence) -> Result<()> {
+        match self.request(id, Request::Presence(presence)).await? {
+            Response::Ack => Ok(()),
+            Response::Error(code) => Err(RemoteError(code).into()),
+            _ => bail!("响应数据非预期"),
+        }
+    }
+}
+fn close_code(connection: &Connection) -> Option<Code> {
+    match connection.close_reason() {
+        Some(ConnectionError::ApplicationClosed(close)) => {
+            Code::from_close_code(close.error_code.into_inner())
+        }
+        _ => None,
+    }
+}
+async fn wait_decision(receiver: oneshot::Receiver<bool>, timeout: Duration) -> Decision {
+    match n0_future::time::timeout(timeout, receiver).await {
+        Ok(Ok(true)) => Decision::Accepted,
+        Ok(Ok(false)) => Decision::Rejected(Code::Rejected),
+        Ok(Err(_)) => Decision::Rejected(Code::Busy),
+        Err(_) => {
+            log::info!("请求等待处理超时，已自动拒绝");
+            Decision::Rejected(Code::Expired)
         }
-        Ok(Some(connection))
     }
 }
+
 impl ProtocolHandler for PersonProtocol {
     async fn accept(&self, connection: Connection) -> Result<(), AcceptError> {
-        self.handle_connection(connection)
-            .await
-            .map_err(|err| AcceptError::User {
+        let remote_id = connection.remote_id();
+        if self.is_blocked(remote_id) {
+            log::info!("已拒绝被屏蔽的{}", remote_id);
+            connection.close(Code::Blocked.close_code().into(), b"blocked");
+            return Ok(());
+        }
+        if !self.rate_limiter.check(remote_id) {
+            log::warn!("{}连接过于频繁，已暂时拒绝", remote_id);
+            connection.close(Code::RateLimited.close_code().into(), b"rate limited");
+            return Ok(());
+        }
+        self.handle_connection(connection).await.map_err(|err| {
+            self.report_error(remote_id, &err);
+            AcceptError::User {
                 source: n0_error::AnyError::from_std_box(err.into()),
                 meta: n0_error::meta(),
+            }
+        })
+    }
+}
+
+fn is_alpn_mismatch(err: &ConnectError) -> bool {
+    matches!(
+        err,
+        ConnectError::Connecting {
+            source: ConnectingError::ConnectionError {
+                source: ConnectionError::ConnectionClosed(close),
+                ..
+            },
+            ..
+        } if close.error_code == TransportErrorCode::crypto(NO_APPLICATION_PROTOCOL)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh::{RelayMode, SecretKey, address_lookup::MemoryLookup, protocol::Router};
+
+    use super::*;
+
+    async fn spawn(
+        lookup: &MemoryLookup,
+        alpns: &[&[u8]],
+        name: &str,
+    ) -> Result<(Router, PersonProtocol)> {
+        spawn_with_config(lookup, alpns, name, Config::default()).await
+    }
+
+    async fn spawn_with_config(
+        lookup: &MemoryLookup,
+        alpns: &[&[u8]],
+        name: &str,
+        config: Config,
+    ) -> Result<(Router, PersonProtocol)> {
+        let endpoint = Endpoint::empty_builder(RelayMode::Disabled)
+            .address_lookup(lookup.clone())
+            .bind()
+            .await?;
+        lookup.add_endpoint_info(endpoint.addr());
+        let protocol = PersonProtocol::new(
+            endpoint.clone(),
+            Person {
+                name: name.to_string(),
+                avatar: None,
+                bio: String::new(),
+            },
+            config,
+        )?;
+        let router = alpns
+            .iter()
+            .fold(Router::builder(endpoint), |builder, alpn| {
+                builder.accept(alpn, protocol.clone())
             })
+            .spawn();
+        Ok((router, protocol))
+    }
+
+    async fn queue(overflow_policy: OverflowPolicy) -> Result<PersonProtocol> {
+        let endpoint = Endpoint::empty_builder(RelayMode::Disabled).bind().await?;
+        PersonProtocol::new(
+            endpoint,
+            Person {
+                name: "alice".to_string(),
+                avatar: None,
+                bio: String::new(),
+            },
+            Config {
+                event_queue_capacity: 1,
+                overflow_policy,
+                ..Config::default()
+            },
+        )
+    }
+
+    fn friend_request(n: u8) -> Event {
+        Event::FriendRequest(FriendRequest {
+            response_sender: oneshot::channel().0,
+            remote_id: SecretKey::from_bytes(&[n; 32]).public(),
+            greeting: None,
+        })
+    }
+
+    fn remote_id(event: Event) -> EndpointId {
+        let Event::FriendRequest(request) = event else {
+            panic!("事件类型非预期");
+        };
+        request.remote_id()
+    }
+
+    #[tokio::test]
+    async fn wait_policy_rejects_after_timeout() -> Result<()> {
+        let protocol = queue(OverflowPolicy::Wait).await?;
+        let timeout = Duration::from_millis(50);
+        assert!(protocol.push_event(friend_request(1), timeout).await?);
+        assert!(!protocol.push_event(friend_request(2), timeout).await?);
+        assert_eq!(
+            remote_id(protocol.next_event().await?),
+            SecretKey::from_bytes(&[1; 32]).public()
+        );
+        assert!(protocol.push_event(friend_request(3), timeout).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reject_new_policy_keeps_queued_event() -> Result<()> {
+        let protocol = queue(OverflowPolicy::RejectNew).await?;
+        assert!(
+            protocol
+                .push_event(friend_request(1), Duration::ZERO)
+                .await?
+        );
+        assert!(
+            !protocol
+                .push_event(friend_request(2), Duration::ZERO)
+                .await?
+        );
+        assert_eq!(
+            remote_id(protocol.next_event().await?),
+            SecretKey::from_bytes(&[1; 32]).public()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_policy_replaces_queued_event() -> Result<()> {
+        let protocol = queue(OverflowPolicy::DropOldest).await?;
+        assert!(
+            protocol
+                .push_event(friend_request(1), Duration::ZERO)
+                .await?
+        );
+        assert!(
+            protocol
+                .push_event(friend_request(2), Duration::ZERO)
+                .await?
+        );
+        assert_eq!(
+            remote_id(protocol.next_event().await?),
+            SecretKey::from_bytes(&[2; 32]).public()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wait_decision_expires() {
+        let timeout = Duration::from_millis(50);
+        let (sender, receiver) = oneshot::channel();
+        sender.send(true).unwrap();
+        assert!(matches!(
+            wait_decision(receiver, timeout).await,
+            Decision::Accepted
+        ));
+        let (sender, receiver) = oneshot::channel();
+        sender.send(false).unwrap();
+        assert!(matches!(
+            wait_decision(receiver, timeout).await,
+            Decision::Rejected(Code::Rejected)
+        ));
+        let (_sender, receiver) = oneshot::channel();
+        assert!(matches!(
+            wait_decision(receiver, timeout).await,
+            Decision::Rejected(Code::Expired)
+        ));
+        let (sender, receiver) = oneshot::channel::<bool>();
+        drop(sender);
+        assert!(matches!(
+            wait_decision(receiver, timeout).await,
+            Decision::Rejected(Code::Busy)
+        ));
+    }
+
+    #[tokio::test]
+    async fn v2_endpoints_negotiate_v2() -> Result<()> {
+        let lookup = MemoryLookup::new();
+        let (alice_router, alice) = spawn(&lookup, &[ALPN_V1, ALPN_V2], "alice").await?;
+        let (bob_router, _) = spawn(&lookup, &[ALPN_V1, ALPN_V2], "bob").await?;
+        let bob_id = bob_router.endpoint().id();
+        let capabilities = alice.capabilities(bob_id).await?;
+        assert_eq!(capabilities.version, 2);
+        assert!(capabilities.supports(Feature::ChatMessage));
+        let profile = alice.request_profile(bob_id, None).await?.unwrap();
+        assert_eq!(profile.person.name, "bob");
+        alice_router.shutdown().await?;
+        bob_router.shutdown().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn profile_not_modified() -> Result<()> {
+        let lookup = MemoryLookup::new();
+        let (alice_router, alice) = spawn(&lookup, &[ALPN_V2], "alice").await?;
+        let (bob_router, bob) = spawn(&lookup, &[ALPN_V2], "bob").await?;
+        let bob_id = bob_router.endpoint().id();
+        let tag = alice.request_profile(bob_id, None).await?.unwrap().tag;
+        assert!(alice.request_profile(bob_id, Some(tag)).await?.is_none());
+        bob.update_person(Person {
+            name: "bobby".to_string(),
+            avatar: None,
+            bio: String::new(),
+        })?;
+        let profile = alice.request_profile(bob_id, Some(tag)).await?.unwrap();
+        assert_eq!(profile.person.name, "bobby");
+        assert_ne!(profile.tag, tag);
+        alice_router.shutdown().await?;
+        bob_router.shutdown().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn refuse_blocked_peer() -> Result<()> {
+        let lookup = MemoryLookup::new();
+        let (alice_router, alice) = spawn(&lookup, &[ALPN_V2], "alice").await?;
+        let (bob_router, bob) = spawn(&lookup, &[ALPN_V2], "bob").await?;
+        let alice_id = alice_router.endpoint().id();
+        let bob_id = bob_router.endpoint().id();
+        bob.block(alice_id);
+        let err = alice.request_profile(bob_id, None).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RemoteError>(),
+            Some(RemoteError(Code::Blocked))
+        ));
+        assert!(bob.unblock(alice_id));
+        assert!(alice.request_profile(bob_id, None).await?.is_some());
+        alice_router.shutdown().await?;
+        bob_router.shutdown().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pool_reuses_connection() -> Result<()> {
+        let lookup = MemoryLookup::new();
+        let (alice_router, alice) = spawn(&lookup, &[ALPN_V2], "alice").await?;
+        let (bob_router, _) = spawn(&lookup, &[ALPN_V2], "bob").await?;
+        let bob_id = bob_router.endpoint().id();
+        alice.request_profile(bob_id, None).await?;
+        let (connection, _) = alice.connection_pool.get(bob_id).unwrap();
+        alice.request_profile(bob_id, None).await?;
+        assert_eq!(
+            alice.connection_pool.get(bob_id).unwrap().0.stable_id(),
+            connection.stable_id()
+        );
+        connection.close(0u32.into(), b"closed");
+        assert!(alice.connection_pool.get(bob_id).is_none());
+        alice.request_profile(bob_id, None).await?;
+        assert_ne!(
+            alice.connection_pool.get(bob_id).unwrap().0.stable_id(),
+            connection.stable_id()
+        );
+        alice_router.shutdown().await?;
+        bob_router.shutdown().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pool_evicts_idle_connection() -> Result<()> {
+        let lookup = MemoryLookup::new();
+        let config = Config {
+            connection_idle_secs: 1,
+            ..Config::default()
+        };
+        let (alice_router, alice) = spawn_with_config(&lookup, &[ALPN_V2], "alice", config).await?;
+        let (bob_router, _) = spawn(&lookup, &[ALPN_V2], "bob").await?;
+        let bob_id = bob_router.endpoint().id();
+        alice.request_profile(bob_id, None).await?;
+        let (connection, _) = alice.connection_pool.get(bob_id).unwrap();
+        n0_future::time::sleep(Duration::from_millis(1600)).await;
+        assert!(alice.connection_pool.get(bob_id).is_none());
+        assert!(connection.close_reason().is_none());
+        alice_router.shutdown().await?;
+        bob_router.shutdown().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_v1_endpoints() -> Result<()> {
+        let lookup = MemoryLookup::new();
+        let (alice_router, alice) = spawn(&lookup, &[ALPN_V1, ALPN_V2], "alice").await?;
+        let (bob_router, _) = spawn(&lookup, &[ALPN_V1], "bob").await?;
+        let capabilities = alice.capabilities(bob_router.endpoint().id()).await?;
+        assert_eq!(capabilities.version, 1);
+        assert!(capabilities.features.is_empty());
+        alice_router.shutdown().await?;
+        bob_router.shutdown().await?;
+        Ok(())
     }
 }
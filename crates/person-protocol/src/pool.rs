@@ -0,0 +1,94 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Weak},
+};
+
+use iroh::{EndpointId, endpoint::Connection};
+use n0_future::time::{Duration, Instant};
+use parking_lot::Mutex;
+
+use crate::{
+    Capabilities,
+    diagnostics::{ConnectionStats, PathTracker},
+};
+
+#[derive(Debug)]
+struct Entry {
+    connection: Connection,
+    capabilities: Capabilities,
+    path_tracker: PathTracker,
+    last_used: Instant,
+}
+
+#[derive(Debug)]
+pub struct ConnectionPool {
+    idle_timeout: Duration,
+    entries: Mutex<HashMap<EndpointId, Entry>>,
+}
+impl ConnectionPool {
+    pub fn new(idle_timeout: Duration) -> Arc<Self> {
+        let pool = Arc::new(Self {
+            idle_timeout,
+            entries: Default::default(),
+        });
+        let weak = Arc::downgrade(&pool);
+        n0_future::task::spawn(evict_loop(weak, idle_timeout));
+        pool
+    }
+    pub fn get(&self, id: EndpointId) -> Option<(Connection, Capabilities)> {
+        let mut entries = self.entries.lock();
+        let entry = entries.get_mut(&id)?;
+        if entry.connection.close_reason().is_some() {
+            entries.remove(&id);
+            return None;
+        }
+        entry.last_used = Instant::now();
+        Some((entry.connection.clone(), entry.capabilities.clone()))
+    }
+    pub fn insert(&self, id: EndpointId, connection: Connection, capabilities: Capabilities) {
+        self.entries.lock().insert(
+            id,
+            Entry {
+                path_tracker: PathTracker::new(&connection),
+                connection,
+                capabilities,
+                last_used: Instant::now(),
+            },
+        );
+    }
+    pub fn stats(&self, id: EndpointId) -> Option<ConnectionStats> {
+        let entries = self.entries.lock();
+        let entry = entries.get(&id)?;
+        Some(entry.path_tracker.stats(&entry.connection))
+    }
+    pub fn remove(&self, id: EndpointId, connection: &Connection) {
+        let mut entries = self.entries.lock();
+        if entries
+            .get(&id)
+            .is_some_and(|v| v.connection.stable_id() == connection.stable_id())
+        {
+            entries.remove(&id);
+        }
+    }
+    fn evict_idle(&self) {
+        let now = Instant::now();
+        self.entries.lock().retain(|id, v| {
+            let keep = v.connection.close_reason().is_none()
+                && now.duration_since(v.last_used) < self.idle_timeout;
+            if !keep {
+                log::debug!("释放与{}的空闲连接", id);
+            }
+            keep
+        });
+    }
+}
+
+async fn evict_loop(pool: Weak<ConnectionPool>, idle_timeout: Duration) {
+    loop {
+        n0_future::time::sleep(idle_timeout / 2).await;
+        match pool.upgrade() {
+            Some(pool) => pool.evict_idle(),
+            None => break,
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use iroh::EndpointId;
+use parking_lot::Mutex;
+use rkyv::Archive;
+use strum::Display;
+
+const MAX_PENDING_PEERS: usize = 1024;
+
+#[derive(
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Presence {
+    Online,
+    Away,
+    Offline,
+}
+
+#[derive(Debug, Clone)]
+pub struct PresenceUpdate {
+    remote_id: EndpointId,
+    presence: Presence,
+}
+impl PresenceUpdate {
+    pub(crate) fn new(remote_id: EndpointId, presence: Presence) -> Self {
+        Self {
+            remote_id,
+            presence,
+        }
+    }
+    pub fn remote_id(&self) -> EndpointId {
+        self.remote_id
+    }
+    pub fn presence(&self) -> Presence {
+        self.presence
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct PresenceQueue {
+    pending: Mutex<HashMap<EndpointId, Presence>>,
+    notify_sender: async_channel::Sender<()>,
+    notify_receiver: async_channel::Receiver<()>,
+}
+impl PresenceQueue {
+    pub fn new() -> Self {
+        let (notify_sender, notify_receiver) = async_channel::bounded(1);
+        Self {
+            pending: Default::default(),
+            notify_sender,
+            notify_receiver,
+        }
+    }
+    pub fn push(&self, update: PresenceUpdate) {
+        let mut pending = self.pending.lock();
+        if pending.len() >= MAX_PENDING_PEERS && !pending.contains_key(&update.remote_id) {
+            log::debug!("待处理的在线状态过多，已丢弃{}的更新", update.remote_id);
+            return;
+        }
+        pending.insert(update.remote_id, update.presence);
+        let _ = self.notify_sender.try_send(());
+    }
+    pub fn take(&self) -> Option<PresenceUpdate> {
+        let mut pending = self.pending.lock();
+        let remote_id = *pending.keys().next()?;
+        let presence = pending.remove(&remote_id)?;
+        Some(PresenceUpdate::new(remote_id, presence))
+    }
+    pub async fn notified(&self) {
+        let _ = self.notify_receiver.recv().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh::SecretKey;
+
+    use super::*;
+
+    fn peer(seed: u8) -> EndpointId {
+        SecretKey::from_bytes(&[seed; 32]).public()
+    }
+
+    #[test]
+    fn coalesces_updates_per_peer() {
+        let queue = PresenceQueue::new();
+        queue.push(PresenceUpdate::new(peer(1), Presence::Online));
+        queue.push(PresenceUpdate::new(peer(1), Presence::Away));
+        queue.push(PresenceUpdate::new(peer(2), Presence::Offline));
+        let mut updates = std::iter::from_fn(|| queue.take())
+            .map(|v| (v.remote_id(), v.presence()))
+            .collect::<Vec<_>>();
+        updates.sort_by_key(|v| v.0);
+        let mut expected = vec![(peer(1), Presence::Away), (peer(2), Presence::Offline)];
+        expected.sort_by_key(|v| v.0);
+        assert_eq!(updates, expected);
+    }
+}
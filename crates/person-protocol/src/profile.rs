@@ -0,0 +1,139 @@
+use std::time::UNIX_EPOCH;
+
+use eyre::{Result, bail};
+use iroh::{EndpointId, SecretKey, Signature};
+use n0_future::time::SystemTime;
+use rkyv::Archive;
+
+use crate::Person;
+
+const SIGNATURE_CONTEXT: &[u8] = b"pupu/person-profile";
+
+#[derive(
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub struct ProfileTag {
+    pub revision: u64,
+    pub hash: [u8; 32],
+}
+impl ProfileTag {
+    fn signing_bytes(&self) -> Vec<u8> {
+        [
+            SIGNATURE_CONTEXT,
+            &self.revision.to_le_bytes(),
+            self.hash.as_slice(),
+        ]
+        .concat()
+    }
+}
+
+#[derive(
+    Archive, rkyv::Serialize, rkyv::Deserialize, Debug, Clone, serde::Serialize, serde::Deserialize,
+)]
+pub struct Profile {
+    pub person: Person,
+    pub tag: ProfileTag,
+    pub signature: Vec<u8>,
+}
+impl Profile {
+    pub fn sign(person: Person, secret_key: &SecretKey) -> Result<Self> {
+        let hash = hash_person(&person)?;
+        let tag = ProfileTag {
+            revision: u64::from_le_bytes(hash[..8].try_into()?),
+            hash,
+        };
+        let signature = secret_key.sign(&tag.signing_bytes()).to_bytes().to_vec();
+        Ok(Self {
+            person,
+            tag,
+            signature,
+        })
+    }
+    pub fn verify(&self, id: EndpointId) -> Result<()> {
+        if hash_person(&self.person)? != self.tag.hash {
+            bail!("资料内容与签名摘要不符");
+        }
+        let signature = Signature::from_bytes(self.signature.as_slice().try_into()?);
+        if id.verify(&self.tag.signing_bytes(), &signature).is_err() {
+            bail!("资料签名验证失败");
+        }
+        Ok(())
+    }
+}
+
+pub fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|v| v.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn hash_person(person: &Person) -> Result<[u8; 32]> {
+    Ok(*blake3::hash(&rkyv::to_bytes::<rkyv::rancor::Error>(person)?).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn person(name: &str) -> Person {
+        Person {
+            name: name.to_string(),
+            avatar: None,
+            bio: String::new(),
+        }
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let secret_key = SecretKey::from_bytes(&[1; 32]);
+        let profile = Profile::sign(person("alice"), &secret_key).unwrap();
+        assert!(profile.verify(secret_key.public()).is_ok());
+        assert!(
+            profile
+                .verify(SecretKey::from_bytes(&[2; 32]).public())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn same_content_keeps_tag() {
+        let secret_key = SecretKey::from_bytes(&[1; 32]);
+        let profile = Profile::sign(person("alice"), &secret_key).unwrap();
+        assert_eq!(
+            Profile::sign(person("alice"), &secret_key).unwrap().tag,
+            profile.tag
+        );
+        assert_ne!(
+            Profile::sign(person("bob"), &secret_key).unwrap().tag,
+            profile.tag
+        );
+    }
+
+    #[test]
+    fn reject_tampered_profile() {
+        let secret_key = SecretKey::from_bytes(&[1; 32]);
+        let profile = Profile::sign(person("alice"), &secret_key).unwrap();
+
+        let mut tampered = profile.clone();
+        tampered.person.name = "mallory".to_string();
+        assert!(tampered.verify(secret_key.public()).is_err());
+
+        let mut tampered = profile.clone();
+        tampered.signature[0] ^= 1;
+        assert!(tampered.verify(secret_key.public()).is_err());
+
+        let mut tampered = profile;
+        tampered.tag.revision += 1;
+        assert!(tampered.verify(secret_key.public()).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use iroh::EndpointId;
+use n0_future::time::{Duration, Instant};
+use parking_lot::Mutex;
+
+use crate::Config;
+
+const MAX_TRACKED_PEERS: usize = 1024;
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+    banned_until: Option<Instant>,
+}
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    burst: f64,
+    per_second: f64,
+    ban_duration: Duration,
+    buckets: Mutex<HashMap<EndpointId, Bucket>>,
+}
+impl RateLimiter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            burst: config.rate_limit_burst.max(1) as f64,
+            per_second: config.rate_limit_per_second as f64,
+            ban_duration: config.rate_limit_ban_duration(),
+            buckets: Default::default(),
+        }
+    }
+    pub fn check(&self, id: EndpointId) -> bool {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock();
+        if buckets.len() >= MAX_TRACKED_PEERS {
+            buckets.retain(|_, v| {
+                v.banned_until.is_some_and(|v| v > now)
+                    || now.duration_since(v.updated_at).as_secs_f64() * self.per_second + v.tokens
+                        < self.burst
+            });
+        }
+        let bucket = buckets.entry(id).or_insert(Bucket {
+            tokens: self.burst,
+            updated_at: now,
+            banned_until: None,
+        });
+        if bucket.banned_until.is_some_and(|v| v > now) {
+            return false;
+        }
+        bucket.tokens = (bucket.tokens
+            + now.duration_since(bucket.updated_at).as_secs_f64() * self.per_second)
+            .min(self.burst);
+        bucket.updated_at = now;
+        if bucket.tokens < 1.0 {
+            bucket.banned_until = Some(now + self.ban_duration);
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+    pub fn penalize(&self, id: EndpointId) {
+        let now = Instant::now();
+        self.buckets.lock().insert(
+            id,
+            Bucket {
+                tokens: 0.0,
+                updated_at: now,
+                banned_until: Some(now + self.ban_duration),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh::SecretKey;
+
+    use super::*;
+
+    fn peer(seed: u8) -> EndpointId {
+        SecretKey::from_bytes(&[seed; 32]).public()
+    }
+    fn limiter(burst: u32, per_second: u32, ban_secs: u64) -> RateLimiter {
+        RateLimiter::new(&Config {
+            rate_limit_burst: burst,
+            rate_limit_per_second: per_second,
+            rate_limit_ban_secs: ban_secs,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn allows_burst_then_bans() {
+        let limiter = limiter(3, 0, 60);
+        let id = peer(1);
+        assert!((0..3).all(|_| limiter.check(id)));
+        assert!(!limiter.check(id));
+        assert!(limiter.buckets.lock()[&id].banned_until.is_some());
+        assert!(!limiter.check(id));
+    }
+
+    #[test]
+    fn peers_have_separate_buckets() {
+        let limiter = limiter(1, 0, 60);
+        assert!(limiter.check(peer(1)));
+        assert!(!limiter.check(peer(1)));
+        assert!(limiter.check(peer(2)));
+    }
+
+    #[test]
+    fn penalize_bans_immediately() {
+        let limiter = limiter(10, 10, 60);
+        let id = peer(1);
+        limiter.penalize(id);
+        assert!(!limiter.check(id));
+        assert!(limiter.check(peer(2)));
+    }
+
+    #[test]
+    fn tokens_refill_after_ban_expires() {
+        let limiter = limiter(1, 1000, 0);
+        let id = peer(1);
+        assert!(limiter.check(id));
+        assert!(!limiter.check(id));
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert!(limiter.check(id));
+    }
+}
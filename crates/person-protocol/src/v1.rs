@@ -0,0 +1,98 @@
+use eyre::{Result, bail};
+use rkyv::Archive;
+
+use crate::{Code, UnsupportedVersion, code::Decision};
+
+pub const ALPN: &[u8] = b"person/v1";
+
+#[derive(Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum Request {
+    Person,
+    Friend,
+    Chat,
+}
+impl From<Request> for crate::Request {
+    fn from(value: Request) -> Self {
+        match value {
+            Request::Person => Self::Profile(None),
+            Request::Friend => Self::Friend(None),
+            Request::Chat => Self::Chat,
+        }
+    }
+}
+impl TryFrom<crate::Request> for Request {
+    type Error = eyre::Report;
+
+    fn try_from(value: crate::Request) -> Result<Self> {
+        match value {
+            crate::Request::Profile(_) => Ok(Self::Person),
+            crate::Request::Friend(_) => Ok(Self::Friend),
+            crate::Request::Chat => Ok(Self::Chat),
+            crate::Request::Presence(_) => Err(UnsupportedVersion {
+                min_version: 1,
+                max_version: 1,
+            }
+            .into()),
+        }
+    }
+}
+
+#[derive(Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum Response {
+    Person(Person),
+    Friend(bool),
+    Chat(bool),
+}
+impl TryFrom<Response> for crate::Response {
+    type Error = eyre::Report;
+
+    fn try_from(value: Response) -> Result<Self> {
+        match value {
+            Response::Person(_) => bail!("对方使用person/v1协议，其资料未经签名，无法验证"),
+            Response::Friend(result) => Ok(Self::Friend(result.into())),
+            Response::Chat(result) => Ok(Self::Chat(result.into())),
+        }
+    }
+}
+impl TryFrom<crate::Response> for Response {
+    type Error = eyre::Report;
+
+    fn try_from(value: crate::Response) -> Result<Self> {
+        Ok(match value {
+            crate::Response::Profile(profile) => Self::Person(profile.person.into()),
+            crate::Response::Friend(decision) => {
+                Self::Friend(matches!(decision, Decision::Accepted))
+            }
+            crate::Response::Chat(decision) => Self::Chat(matches!(decision, Decision::Accepted)),
+            crate::Response::ProfileNotModified
+            | crate::Response::Ack
+            | crate::Response::Error(_) => bail!("该响应无法以person/v1协议表示"),
+        })
+    }
+}
+
+#[derive(Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct Person {
+    name: String,
+    avatar: Option<Vec<u8>>,
+    bio: String,
+}
+impl From<crate::Person> for Person {
+    fn from(value: crate::Person) -> Self {
+        Self {
+            name: value.name,
+            avatar: None,
+            bio: value.bio,
+        }
+    }
+}
+
+impl From<bool> for Decision {
+    fn from(value: bool) -> Self {
+        if value {
+            Decision::Accepted
+        } else {
+            Decision::Rejected(Code::Rejected)
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use rkyv::Archive;
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
+
+pub const ALPN: &[u8] = b"person/v2";
+pub const MIN_VERSION: u16 = 2;
+pub const MAX_VERSION: u16 = 2;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Display,
+    EnumString,
+    EnumIter,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    ChatMessage,
+    Presence,
+    TypingIndicator,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Capabilities {
+    pub version: u16,
+    pub features: Vec<Feature>,
+}
+impl Capabilities {
+    pub fn v1() -> Self {
+        Self {
+            version: 1,
+            features: Vec::new(),
+        }
+    }
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.features.contains(&feature)
+    }
+}
+
+#[derive(Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct Hello {
+    min_version: u16,
+    max_version: u16,
+    features: Vec<String>,
+}
+impl Hello {
+    pub fn local() -> Self {
+        Self {
+            min_version: MIN_VERSION,
+            max_version: MAX_VERSION,
+            features: Feature::iter().map(|v| v.to_string()).collect(),
+        }
+    }
+    pub fn negotiate(&self) -> HelloResponse {
+        let version = self.max_version.min(MAX_VERSION);
+        if version < self.min_version.max(MIN_VERSION) {
+            return HelloResponse::Unsupported {
+                min_version: MIN_VERSION,
+                max_version: MAX_VERSION,
+            };
+        }
+        HelloResponse::Accepted {
+            version,
+            features: self
+                .features
+                .iter()
+                .filter(|v| v.parse::<Feature>().is_ok())
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+#[derive(Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum HelloResponse {
+    Accepted { version: u16, features: Vec<String> },
+    Unsupported { min_version: u16, max_version: u16 },
+}
+impl HelloResponse {
+    pub fn capabilities(self) -> Result<Capabilities, UnsupportedVersion> {
+        match self {
+            HelloResponse::Accepted { version, features } => Ok(Capabilities {
+                version,
+                features: features.iter().filter_map(|v| v.parse().ok()).collect(),
+            }),
+            HelloResponse::Unsupported {
+                min_version,
+                max_version,
+            } => Err(UnsupportedVersion {
+                min_version,
+                max_version,
+            }),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UnsupportedVersion {
+    pub min_version: u16,
+    pub max_version: u16,
+}
+impl std::fmt::Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "对方不支持本端的协议版本（对方支持{}至{}，本端支持{}至{}）",
+            self.min_version, self.max_version, MIN_VERSION, MAX_VERSION
+        )
+    }
+}
+impl std::error::Error for UnsupportedVersion {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello(min_version: u16, max_version: u16, features: &[&str]) -> Hello {
+        Hello {
+            min_version,
+            max_version,
+            features: features.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn negotiate_local_hello() {
+        let capabilities = Hello::local().negotiate().capabilities().unwrap();
+        assert_eq!(capabilities.version, MAX_VERSION);
+        assert!(Feature::iter().all(|v| capabilities.supports(v)));
+    }
+
+    #[test]
+    fn negotiate_picks_highest_common_version() {
+        let HelloResponse::Accepted { version, .. } = hello(1, MAX_VERSION + 5, &[]).negotiate()
+        else {
+            panic!("版本协商失败");
+        };
+        assert_eq!(version, MAX_VERSION);
+    }
+
+    #[test]
+    fn negotiate_rejects_disjoint_versions() {
+        assert!(matches!(
+            hello(MAX_VERSION + 1, MAX_VERSION + 2, &[]).negotiate(),
+            HelloResponse::Unsupported {
+                min_version: MIN_VERSION,
+                max_version: MAX_VERSION,
+            }
+        ));
+        let err = hello(1, MIN_VERSION - 1, &[])
+            .negotiate()
+            .capabilities()
+            .unwrap_err();
+        assert_eq!(err.min_version, MIN_VERSION);
+        assert_eq!(err.max_version, MAX_VERSION);
+    }
+
+    #[test]
+    fn negotiate_drops_unknown_features() {
+        let capabilities = hello(MIN_VERSION, MAX_VERSION, &["chat_message", "teleport"])
+            .negotiate()
+            .capabilities()
+            .unwrap();
+        assert_eq!(capabilities.features, vec![Feature::ChatMessage]);
+    }
+}
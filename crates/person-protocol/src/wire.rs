@@ -0,0 +1,45 @@
+use eyre::Result;
+use iroh::endpoint::{ReadToEndError, RecvStream, SendStream};
+use rkyv::{
+    Archive, Deserialize, Serialize,
+    api::high::{HighDeserializer, HighSerializer, HighValidator},
+    bytecheck::CheckBytes,
+    rancor::Error,
+    ser::allocator::ArenaHandle,
+    util::AlignedVec,
+};
+
+pub async fn write<T>(send: &mut SendStream, value: &T) -> Result<()>
+where
+    T: for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, Error>>,
+{
+    send.write_all(&rkyv::to_bytes::<Error>(value)?).await?;
+    send.finish()?;
+    Ok(())
+}
+pub async fn read<T>(recv: &mut RecvStream, size_limit: usize) -> Result<T>
+where
+    T: Archive,
+    T::Archived:
+        for<'a> CheckBytes<HighValidator<'a, Error>> + Deserialize<T, HighDeserializer<Error>>,
+{
+    let bytes = recv
+        .read_to_end(size_limit)
+        .await
+        .map_err(|err| match err {
+            ReadToEndError::TooLong => eyre::Report::new(MessageTooLarge(size_limit)),
+            err => err.into(),
+        })?;
+    let mut data = AlignedVec::<16>::new();
+    data.extend_from_slice(&bytes);
+    Ok(rkyv::from_bytes::<T, Error>(&data)?)
+}
+
+#[derive(Debug)]
+pub struct MessageTooLarge(usize);
+impl std::fmt::Display for MessageTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "消息超过大小限制（{}字节）", self.0)
+    }
+}
+impl std::error::Error for MessageTooLarge {}
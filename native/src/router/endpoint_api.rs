@@ -1,98 +1,316 @@
-use std::sync::Arc;
+mod identity;
 
-use endpoint::{Endpoint, RelayConfig};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use endpoint::{Endpoint, StoreBackend};
+use eyre::bail;
 use sharded_slab::Slab;
-use tauri::{Runtime, Window};
+use tauri::{Runtime, Window, ipc::Channel};
 use utils::option_ext::OptionGet;
 
-use crate::error::MapStringError;
+use crate::{error::MapStringError, router::endpoint_api::identity::Identities};
+
+const STORE_DIR: &str = "store";
+const FILES_DIR: &str = "files";
+const IDENTITIES_DIR: &str = "identities";
 
 #[taurpc::procedures(path = "endpoint")]
 pub trait EndpointApi {
-    async fn generate_secret_key() -> Vec<u8>;
-    async fn get_secret_key_id(secret_key: Vec<u8>) -> Result<String, String>;
+    async fn create_identity<R: Runtime>(
+        window: Window<R>,
+        passphrase: String,
+    ) -> Result<String, String>;
+    async fn import_identity<R: Runtime>(
+        window: Window<R>,
+        secret_key: Vec<u8>,
+        passphrase: String,
+    ) -> Result<String, String>;
+    async fn list_identities<R: Runtime>(window: Window<R>) -> Result<Vec<String>, String>;
+    async fn import_identity_mnemonic<R: Runtime>(
+        window: Window<R>,
+        mnemonic: String,
+        passphrase: String,
+    ) -> Result<String, String>;
+    async fn export_identity_mnemonic<R: Runtime>(
+        window: Window<R>,
+        id: String,
+        passphrase: String,
+    ) -> Result<String, String>;
+    async fn unlock_identity<R: Runtime>(
+        window: Window<R>,
+        id: String,
+        passphrase: String,
+    ) -> Result<(), String>;
+    async fn lock_identity(id: String);
+    async fn change_identity_passphrase<R: Runtime>(
+        window: Window<R>,
+        id: String,
+        passphrase: String,
+        new_passphrase: String,
+    ) -> Result<(), String>;
+    async fn delete_identity<R: Runtime>(
+        window: Window<R>,
+        id: String,
+        passphrase: String,
+    ) -> Result<(), String>;
     async fn generate_group_id() -> String;
-    async fn generate_ticket(group_id: String, bootstrap: Vec<String>) -> Result<String, String>;
+    async fn verify_ticket(ticket: String) -> Result<serde_json::Value, String>;
     async fn open_endpoint<R: Runtime>(
         window: Window<R>,
-        secret_key: Vec<u8>,
+        identity_id: String,
         person: serde_json::Value,
-        relay_configs: Vec<serde_json::Value>,
+        config: serde_json::Value,
     ) -> Result<usize, String>;
     async fn close_endpoint(handle: usize) -> Result<(), String>;
     async fn id(handle: usize) -> Result<String, String>;
-    async fn person_protocol_next_event(handle: usize) -> Result<String, String>;
-    async fn person_protocol_event(
+    async fn network_report(handle: usize) -> Result<serde_json::Value, String>;
+    async fn next_connectivity_event(handle: usize) -> Result<Option<serde_json::Value>, String>;
+    async fn person_protocol_next_event(handle: usize) -> Result<serde_json::Value, String>;
+    async fn inspect_event(handle: usize, event_id: usize) -> Result<serde_json::Value, String>;
+    async fn accept_event(handle: usize, event_id: usize) -> Result<Option<usize>, String>;
+    async fn reject_event(handle: usize, event_id: usize) -> Result<(), String>;
+    async fn profile(handle: usize) -> Result<serde_json::Value, String>;
+    async fn update_person(handle: usize, person: serde_json::Value) -> Result<(), String>;
+    async fn set_avatar(handle: usize, avatar: Vec<u8>) -> Result<String, String>;
+    async fn fetch_avatar(handle: usize, id: String, hash: String) -> Result<Vec<u8>, String>;
+    async fn add_blob(
+        handle: usize,
+        data: Vec<u8>,
+        owner: serde_json::Value,
+        expires_in_secs: Option<u64>,
+    ) -> Result<String, String>;
+    async fn add_blob_from_path<R: Runtime>(
+        window: Window<R>,
         handle: usize,
-        method: String,
+        path: String,
+        owner: serde_json::Value,
+        expires_in_secs: Option<u64>,
+    ) -> Result<String, String>;
+    async fn download_blob(
+        handle: usize,
+        ticket: String,
+        owner: serde_json::Value,
+        expires_in_secs: Option<u64>,
+        channel: Channel<u64>,
+    ) -> Result<String, String>;
+    async fn read_blob(handle: usize, hash: String) -> Result<Vec<u8>, String>;
+    async fn export_blob<R: Runtime>(
+        window: Window<R>,
+        handle: usize,
+        hash: String,
+        path: String,
+    ) -> Result<(), String>;
+    async fn release_blobs(handle: usize, owner: serde_json::Value) -> Result<usize, String>;
+    async fn blob_usage(handle: usize) -> Result<serde_json::Value, String>;
+    async fn collect_garbage(handle: usize) -> Result<serde_json::Value, String>;
+    async fn request_person(
+        handle: usize,
+        id: String,
+        tag: Option<serde_json::Value>,
+    ) -> Result<Option<serde_json::Value>, String>;
+    async fn request_friend(
+        handle: usize,
+        id: String,
+        message: String,
     ) -> Result<serde_json::Value, String>;
-    async fn request_person(handle: usize, id: String) -> Result<serde_json::Value, String>;
-    async fn request_friend(handle: usize, id: String) -> Result<bool, String>;
-    async fn request_chat(handle: usize, id: String) -> Result<Option<usize>, String>;
+    async fn request_chat(handle: usize, id: String) -> Result<serde_json::Value, String>;
+    async fn send_message(
+        handle: usize,
+        chat_handle: usize,
+        message: serde_json::Value,
+    ) -> Result<(), String>;
+    async fn next_message(
+        handle: usize,
+        chat_handle: usize,
+    ) -> Result<Option<serde_json::Value>, String>;
+    async fn send_typing(handle: usize, chat_handle: usize, typing: bool) -> Result<(), String>;
+    async fn next_typing(handle: usize, chat_handle: usize) -> Result<Option<bool>, String>;
+    async fn set_presence(
+        handle: usize,
+        presence: serde_json::Value,
+        ids: Vec<String>,
+    ) -> Result<Vec<String>, String>;
+    async fn chat_stats(handle: usize, chat_handle: usize) -> Result<serde_json::Value, String>;
+    async fn peer_stats(handle: usize, id: String) -> Result<Option<serde_json::Value>, String>;
+    async fn close_chat(handle: usize, chat_handle: usize) -> Result<(), String>;
+    async fn block(handle: usize, id: String) -> Result<(), String>;
+    async fn unblock(handle: usize, id: String) -> Result<bool, String>;
+    async fn blocked_list(handle: usize) -> Result<Vec<String>, String>;
+    async fn generate_ticket(
+        handle: usize,
+        group_id: String,
+        name: String,
+        bootstrap: Vec<String>,
+        expires_in_secs: Option<u64>,
+    ) -> Result<String, String>;
     async fn subscribe_group(handle: usize, ticket: String) -> Result<usize, String>;
+    async fn broadcast(
+        handle: usize,
+        group_handle: usize,
+        message: serde_json::Value,
+    ) -> Result<(), String>;
+    async fn next_group_message(
+        handle: usize,
+        group_handle: usize,
+    ) -> Result<Option<serde_json::Value>, String>;
+    async fn next_member_event(
+        handle: usize,
+        group_handle: usize,
+    ) -> Result<Option<serde_json::Value>, String>;
+    async fn group_dropped_messages(handle: usize, group_handle: usize) -> Result<usize, String>;
+    async fn leave_group(handle: usize, group_handle: usize) -> Result<(), String>;
 }
 
 #[derive(Clone, Default)]
 pub struct EndpointApiImpl {
     endpoint_pool: Arc<Slab<Endpoint>>,
+    identities: Arc<Identities>,
 }
 #[taurpc::resolvers]
 impl EndpointApi for EndpointApiImpl {
-    async fn generate_secret_key(self) -> Vec<u8> {
-        endpoint::generate_secret_key()
+    async fn create_identity<R: Runtime>(
+        self,
+        window: Window<R>,
+        passphrase: String,
+    ) -> Result<String, String> {
+        async {
+            self.identities
+                .create(&data_path(&window, IDENTITIES_DIR)?, passphrase)
+                .await
+        }
+        .await
+        .mse()
     }
-    async fn get_secret_key_id(self, secret_key: Vec<u8>) -> Result<String, String> {
-        endpoint::get_secret_key_id(secret_key).mse()
+    async fn import_identity<R: Runtime>(
+        self,
+        window: Window<R>,
+        secret_key: Vec<u8>,
+        passphrase: String,
+    ) -> Result<String, String> {
+        async {
+            self.identities
+                .import(&data_path(&window, IDENTITIES_DIR)?, secret_key, passphrase)
+                .await
+        }
+        .await
+        .mse()
     }
-    async fn generate_group_id(self) -> String {
-        endpoint::generate_group_id()
+    async fn list_identities<R: Runtime>(self, window: Window<R>) -> Result<Vec<String>, String> {
+        async {
+            self.identities
+                .list(&data_path(&window, IDENTITIES_DIR)?)
+                .await
+        }
+        .await
+        .mse()
     }
-    async fn generate_ticket(
+    async fn import_identity_mnemonic<R: Runtime>(
         self,
-        group_id: String,
-        bootstrap: Vec<String>,
+        window: Window<R>,
+        mnemonic: String,
+        passphrase: String,
     ) -> Result<String, String> {
-        endpoint::generate_ticket(group_id, bootstrap).mse()
+        async {
+            self.identities
+                .import_mnemonic(&data_path(&window, IDENTITIES_DIR)?, mnemonic, passphrase)
+                .await
+        }
+        .await
+        .mse()
+    }
+    async fn export_identity_mnemonic<R: Runtime>(
+        self,
+        window: Window<R>,
+        id: String,
+        passphrase: String,
+    ) -> Result<String, String> {
+        async {
+            self.identities
+                .export_mnemonic(&data_path(&window, IDENTITIES_DIR)?, id, passphrase)
+                .await
+        }
+        .await
+        .mse()
+    }
+    async fn unlock_identity<R: Runtime>(
+        self,
+        window: Window<R>,
+        id: String,
+        passphrase: String,
+    ) -> Result<(), String> {
+        async {
+            self.identities
+                .unlock(&data_path(&window, IDENTITIES_DIR)?, id, passphrase)
+                .await
+        }
+        .await
+        .mse()
+    }
+    async fn lock_identity(self, id: String) {
+        self.identities.lock(&id).await
+    }
+    async fn change_identity_passphrase<R: Runtime>(
+        self,
+        window: Window<R>,
+        id: String,
+        passphrase: String,
+        new_passphrase: String,
+    ) -> Result<(), String> {
+        async {
+            self.identities
+                .change_passphrase(
+                    &data_path(&window, IDENTITIES_DIR)?,
+                    id,
+                    passphrase,
+                    new_passphrase,
+                )
+                .await
+        }
+        .await
+        .mse()
+    }
+    async fn delete_identity<R: Runtime>(
+        self,
+        window: Window<R>,
+        id: String,
+        passphrase: String,
+    ) -> Result<(), String> {
+        async {
+            self.identities
+                .delete(&data_path(&window, IDENTITIES_DIR)?, id, passphrase)
+                .await
+        }
+        .await
+        .mse()
+    }
+    async fn generate_group_id(self) -> String {
+        endpoint::generate_group_id()
+    }
+    async fn verify_ticket(self, ticket: String) -> Result<serde_json::Value, String> {
+        async { eyre::Ok(serde_json::to_value(endpoint::verify_ticket(ticket)?)?) }
+            .await
+            .mse()
     }
     async fn open_endpoint<R: Runtime>(
         self,
-        #[allow(unused_variables)] window: Window<R>,
-        secret_key: Vec<u8>,
+        window: Window<R>,
+        identity_id: String,
         person: serde_json::Value,
-        relay_configs: Vec<serde_json::Value>,
+        config: serde_json::Value,
     ) -> Result<usize, String> {
         async {
-            let store_path;
-            #[cfg(not(debug_assertions))]
-            {
-                use tauri::Manager;
-
-                store_path = window.path().app_local_data_dir()?.join("store");
-            }
-            #[cfg(debug_assertions)]
-            {
-                #[cfg(target_os = "android")]
-                {
-                    use tauri::Manager;
-
-                    store_path = window.path().app_local_data_dir()?.join("store");
-                }
-                #[cfg(not(target_os = "android"))]
-                {
-                    store_path = "store";
-                }
-            }
+            let secret_key = self.identities.secret_key(&identity_id).await?;
             eyre::Ok(
                 self.endpoint_pool
                     .insert(
                         Endpoint::new(
-                            secret_key,
+                            secret_key.to_vec(),
                             serde_json::from_value(person)?,
-                            store_path,
-                            relay_configs
-                                .into_iter()
-                                .map(|v| serde_json::from_value::<RelayConfig>(v))
-                                .collect::<Result<_, _>>()?,
+                            StoreBackend::Path(data_path(&window, STORE_DIR)?),
+                            serde_json::from_value(config)?,
                         )
                         .await?,
                     )
@@ -115,62 +333,448 @@ impl EndpointApi for EndpointApiImpl {
     async fn id(self, handle: usize) -> Result<String, String> {
         Ok(self.endpoint_pool.get(handle).get().mse()?.id())
     }
-    async fn person_protocol_next_event(self, handle: usize) -> Result<String, String> {
+    async fn network_report(self, handle: usize) -> Result<serde_json::Value, String> {
+        async {
+            eyre::Ok(serde_json::to_value(
+                self.endpoint_pool.get(handle).get()?.network_report(),
+            )?)
+        }
+        .await
+        .mse()
+    }
+    async fn next_connectivity_event(
+        self,
+        handle: usize,
+    ) -> Result<Option<serde_json::Value>, String> {
+        async {
+            eyre::Ok(
+                self.endpoint_pool
+                    .get_owned(handle)
+                    .get()?
+                    .next_connectivity_event()
+                    .await
+                    .map(serde_json::to_value)
+                    .transpose()?,
+            )
+        }
+        .await
+        .mse()
+    }
+    async fn person_protocol_next_event(self, handle: usize) -> Result<serde_json::Value, String> {
+        async {
+            eyre::Ok(serde_json::to_value(
+                self.endpoint_pool
+                    .get_owned(handle)
+                    .get()?
+                    .person_protocol_next_event()
+                    .await?,
+            )?)
+        }
+        .await
+        .mse()
+    }
+    async fn inspect_event(
+        self,
+        handle: usize,
+        event_id: usize,
+    ) -> Result<serde_json::Value, String> {
+        async {
+            eyre::Ok(serde_json::to_value(
+                self.endpoint_pool
+                    .get(handle)
+                    .get()?
+                    .inspect_event(event_id)?,
+            )?)
+        }
+        .await
+        .mse()
+    }
+    async fn accept_event(self, handle: usize, event_id: usize) -> Result<Option<usize>, String> {
+        self.endpoint_pool
+            .get(handle)
+            .get()
+            .mse()?
+            .accept_event(event_id)
+            .mse()
+    }
+    async fn reject_event(self, handle: usize, event_id: usize) -> Result<(), String> {
+        self.endpoint_pool
+            .get(handle)
+            .get()
+            .mse()?
+            .reject_event(event_id)
+            .mse()
+    }
+    async fn profile(self, handle: usize) -> Result<serde_json::Value, String> {
+        async {
+            eyre::Ok(serde_json::to_value(
+                self.endpoint_pool.get(handle).get()?.profile(),
+            )?)
+        }
+        .await
+        .mse()
+    }
+    async fn update_person(self, handle: usize, person: serde_json::Value) -> Result<(), String> {
+        async {
+            self.endpoint_pool
+                .get(handle)
+                .get()?
+                .update_person(serde_json::from_value(person)?)?;
+            eyre::Ok(())
+        }
+        .await
+        .mse()
+    }
+    async fn set_avatar(self, handle: usize, avatar: Vec<u8>) -> Result<String, String> {
         Ok(self
             .endpoint_pool
             .get_owned(handle)
             .get()
             .mse()?
-            .person_protocol_next_event()
+            .set_avatar(avatar)
             .await
             .mse()?)
     }
-    async fn person_protocol_event(
+    async fn fetch_avatar(
         self,
         handle: usize,
-        method: String,
-    ) -> Result<serde_json::Value, String> {
+        id: String,
+        hash: String,
+    ) -> Result<Vec<u8>, String> {
         Ok(self
             .endpoint_pool
-            .get(handle)
+            .get_owned(handle)
             .get()
             .mse()?
-            .person_protocol_event(method)
+            .fetch_avatar(id, hash)
+            .await
             .mse()?)
     }
-    async fn request_person(self, handle: usize, id: String) -> Result<serde_json::Value, String> {
+    async fn add_blob(
+        self,
+        handle: usize,
+        data: Vec<u8>,
+        owner: serde_json::Value,
+        expires_in_secs: Option<u64>,
+    ) -> Result<String, String> {
+        async {
+            self.endpoint_pool
+                .get_owned(handle)
+                .get()?
+                .add_blob(data, serde_json::from_value(owner)?, expires_in_secs)
+                .await
+        }
+        .await
+        .mse()
+    }
+    async fn add_blob_from_path<R: Runtime>(
+        self,
+        window: Window<R>,
+        handle: usize,
+        path: String,
+        owner: serde_json::Value,
+        expires_in_secs: Option<u64>,
+    ) -> Result<String, String> {
+        async {
+            let path = scoped_path(&data_path(&window, FILES_DIR)?, &path)?;
+            self.endpoint_pool
+                .get_owned(handle)
+                .get()?
+                .add_blob_from_path(path, serde_json::from_value(owner)?, expires_in_secs)
+                .await
+        }
+        .await
+        .mse()
+    }
+    async fn download_blob(
+        self,
+        handle: usize,
+        ticket: String,
+        owner: serde_json::Value,
+        expires_in_secs: Option<u64>,
+        channel: Channel<u64>,
+    ) -> Result<String, String> {
+        async {
+            self.endpoint_pool
+                .get_owned(handle)
+                .get()?
+                .download_blob(
+                    ticket,
+                    serde_json::from_value(owner)?,
+                    expires_in_secs,
+                    |downloaded| {
+                        if let Err(err) = channel.send(downloaded) {
+                            log::error!("{}", err);
+                        }
+                    },
+                )
+                .await
+        }
+        .await
+        .mse()
+    }
+    async fn read_blob(self, handle: usize, hash: String) -> Result<Vec<u8>, String> {
+        Ok(self
+            .endpoint_pool
+            .get_owned(handle)
+            .get()
+            .mse()?
+            .read_blob(hash)
+            .await
+            .mse()?)
+    }
+    async fn export_blob<R: Runtime>(
+        self,
+        window: Window<R>,
+        handle: usize,
+        hash: String,
+        path: String,
+    ) -> Result<(), String> {
+        async {
+            let path = scoped_path(&data_path(&window, FILES_DIR)?, &path)?;
+            self.endpoint_pool
+                .get_owned(handle)
+                .get()?
+                .export_blob(hash, path)
+                .await
+        }
+        .await
+        .mse()
+    }
+    async fn release_blobs(self, handle: usize, owner: serde_json::Value) -> Result<usize, String> {
+        async {
+            self.endpoint_pool
+                .get_owned(handle)
+                .get()?
+                .release_blobs(serde_json::from_value(owner)?)
+                .await
+        }
+        .await
+        .mse()
+    }
+    async fn blob_usage(self, handle: usize) -> Result<serde_json::Value, String> {
         async {
             eyre::Ok(serde_json::to_value(
-                &self
-                    .endpoint_pool
+                self.endpoint_pool
                     .get_owned(handle)
                     .get()?
-                    .request_person(id)
+                    .blob_usage()
                     .await?,
             )?)
         }
         .await
         .mse()
     }
-    async fn request_friend(self, handle: usize, id: String) -> Result<bool, String> {
+    async fn collect_garbage(self, handle: usize) -> Result<serde_json::Value, String> {
+        async {
+            eyre::Ok(serde_json::to_value(
+                self.endpoint_pool
+                    .get_owned(handle)
+                    .get()?
+                    .collect_garbage()
+                    .await?,
+            )?)
+        }
+        .await
+        .mse()
+    }
+    async fn request_person(
+        self,
+        handle: usize,
+        id: String,
+        tag: Option<serde_json::Value>,
+    ) -> Result<Option<serde_json::Value>, String> {
+        async {
+            eyre::Ok(
+                self.endpoint_pool
+                    .get_owned(handle)
+                    .get()?
+                    .request_person(id, tag.map(serde_json::from_value).transpose()?)
+                    .await?
+                    .map(serde_json::to_value)
+                    .transpose()?,
+            )
+        }
+        .await
+        .mse()
+    }
+    async fn request_friend(
+        self,
+        handle: usize,
+        id: String,
+        message: String,
+    ) -> Result<serde_json::Value, String> {
+        async {
+            eyre::Ok(serde_json::to_value(
+                self.endpoint_pool
+                    .get_owned(handle)
+                    .get()?
+                    .request_friend(id, message)
+                    .await?,
+            )?)
+        }
+        .await
+        .mse()
+    }
+    async fn request_chat(self, handle: usize, id: String) -> Result<serde_json::Value, String> {
+        async {
+            eyre::Ok(serde_json::to_value(
+                self.endpoint_pool
+                    .get_owned(handle)
+                    .get()?
+                    .request_chat(id)
+                    .await?,
+            )?)
+        }
+        .await
+        .mse()
+    }
+    async fn send_message(
+        self,
+        handle: usize,
+        chat_handle: usize,
+        message: serde_json::Value,
+    ) -> Result<(), String> {
+        async {
+            self.endpoint_pool
+                .get_owned(handle)
+                .get()?
+                .send_message(chat_handle, serde_json::from_value(message)?)
+                .await?;
+            eyre::Ok(())
+        }
+        .await
+        .mse()
+    }
+    async fn next_message(
+        self,
+        handle: usize,
+        chat_handle: usize,
+    ) -> Result<Option<serde_json::Value>, String> {
+        async {
+            eyre::Ok(
+                self.endpoint_pool
+                    .get_owned(handle)
+                    .get()?
+                    .next_message(chat_handle)
+                    .await?
+                    .map(serde_json::to_value)
+                    .transpose()?,
+            )
+        }
+        .await
+        .mse()
+    }
+    async fn send_typing(
+        self,
+        handle: usize,
+        chat_handle: usize,
+        typing: bool,
+    ) -> Result<(), String> {
+        self.endpoint_pool
+            .get(handle)
+            .get()
+            .mse()?
+            .send_typing(chat_handle, typing)
+            .mse()
+    }
+    async fn next_typing(self, handle: usize, chat_handle: usize) -> Result<Option<bool>, String> {
         Ok(self
             .endpoint_pool
             .get_owned(handle)
             .get()
             .mse()?
-            .request_friend(id)
+            .next_typing(chat_handle)
             .await
             .mse()?)
     }
-    async fn request_chat(self, handle: usize, id: String) -> Result<Option<usize>, String> {
-        Ok(self
-            .endpoint_pool
+    async fn set_presence(
+        self,
+        handle: usize,
+        presence: serde_json::Value,
+        ids: Vec<String>,
+    ) -> Result<Vec<String>, String> {
+        async {
+            self.endpoint_pool
+                .get_owned(handle)
+                .get()?
+                .set_presence(serde_json::from_value(presence)?, ids)
+                .await
+        }
+        .await
+        .mse()
+    }
+    async fn chat_stats(
+        self,
+        handle: usize,
+        chat_handle: usize,
+    ) -> Result<serde_json::Value, String> {
+        async {
+            eyre::Ok(serde_json::to_value(
+                self.endpoint_pool
+                    .get(handle)
+                    .get()?
+                    .chat_stats(chat_handle)?,
+            )?)
+        }
+        .await
+        .mse()
+    }
+    async fn peer_stats(
+        self,
+        handle: usize,
+        id: String,
+    ) -> Result<Option<serde_json::Value>, String> {
+        async {
+            eyre::Ok(
+                self.endpoint_pool
+                    .get(handle)
+                    .get()?
+                    .peer_stats(id)?
+                    .map(serde_json::to_value)
+                    .transpose()?,
+            )
+        }
+        .await
+        .mse()
+    }
+    async fn close_chat(self, handle: usize, chat_handle: usize) -> Result<(), String> {
+        self.endpoint_pool
+            .get(handle)
+            .get()
+            .mse()?
+            .close_chat(chat_handle);
+        Ok(())
+    }
+    async fn block(self, handle: usize, id: String) -> Result<(), String> {
+        self.endpoint_pool.get(handle).get().mse()?.block(id).mse()
+    }
+    async fn unblock(self, handle: usize, id: String) -> Result<bool, String> {
+        self.endpoint_pool
+            .get(handle)
+            .get()
+            .mse()?
+            .unblock(id)
+            .mse()
+    }
+    async fn blocked_list(self, handle: usize) -> Result<Vec<String>, String> {
+        Ok(self.endpoint_pool.get(handle).get().mse()?.blocked_list())
+    }
+    async fn generate_ticket(
+        self,
+        handle: usize,
+        group_id: String,
+        name: String,
+        bootstrap: Vec<String>,
+        expires_in_secs: Option<u64>,
+    ) -> Result<String, String> {
+        self.endpoint_pool
             .get_owned(handle)
             .get()
             .mse()?
-            .request_chat(id)
+            .generate_ticket(group_id, name, bootstrap, expires_in_secs)
             .await
-            .mse()?)
+            .mse()
     }
     async fn subscribe_group(self, handle: usize, ticket: String) -> Result<usize, String> {
         Ok(self
@@ -182,4 +786,123 @@ impl EndpointApi for EndpointApiImpl {
             .await
             .mse()?)
     }
+    async fn broadcast(
+        self,
+        handle: usize,
+        group_handle: usize,
+        message: serde_json::Value,
+    ) -> Result<(), String> {
+        async {
+            self.endpoint_pool
+                .get_owned(handle)
+                .get()?
+                .broadcast(group_handle, serde_json::from_value(message)?)
+                .await?;
+            eyre::Ok(())
+        }
+        .await
+        .mse()
+    }
+    async fn next_group_message(
+        self,
+        handle: usize,
+        group_handle: usize,
+    ) -> Result<Option<serde_json::Value>, String> {
+        async {
+            eyre::Ok(
+                self.endpoint_pool
+                    .get_owned(handle)
+                    .get()?
+                    .next_group_message(group_handle)
+                    .await?
+                    .map(serde_json::to_value)
+                    .transpose()?,
+            )
+        }
+        .await
+        .mse()
+    }
+    async fn next_member_event(
+        self,
+        handle: usize,
+        group_handle: usize,
+    ) -> Result<Option<serde_json::Value>, String> {
+        async {
+            eyre::Ok(
+                self.endpoint_pool
+                    .get_owned(handle)
+                    .get()?
+                    .next_member_event(group_handle)
+                    .await?
+                    .map(serde_json::to_value)
+                    .transpose()?,
+            )
+        }
+        .await
+        .mse()
+    }
+    async fn group_dropped_messages(
+        self,
+        handle: usize,
+        group_handle: usize,
+    ) -> Result<usize, String> {
+        self.endpoint_pool
+            .get(handle)
+            .get()
+            .mse()?
+            .group_dropped_messages(group_handle)
+            .mse()
+    }
+    async fn leave_group(self, handle: usize, group_handle: usize) -> Result<(), String> {
+        self.endpoint_pool
+            .get(handle)
+            .get()
+            .mse()?
+            .leave_group(group_handle);
+        Ok(())
+    }
+}
+
+fn data_path<R: Runtime>(
+    #[allow(unused_variables)] window: &Window<R>,
+    name: &str,
+) -> eyre::Result<PathBuf> {
+    let path;
+    #[cfg(not(debug_assertions))]
+    {
+        use tauri::Manager;
+
+        path = window.path().app_local_data_dir()?.join(name);
+    }
+    #[cfg(debug_assertions)]
+    {
+        #[cfg(target_os = "android")]
+        {
+            use tauri::Manager;
+
+            path = window.path().app_local_data_dir()?.join(name);
+        }
+        #[cfg(not(target_os = "android"))]
+        {
+            path = name.into();
+        }
+    }
+    Ok(path)
+}
+
+fn scoped_path(root: &Path, path: &str) -> eyre::Result<PathBuf> {
+    std::fs::create_dir_all(root)?;
+    let root = root.canonicalize()?;
+    let path = root.join(path);
+    let (Some(parent), Some(file_name)) = (path.parent(), path.file_name()) else {
+        bail!("无效的文件路径");
+    };
+    let path = match path.canonicalize() {
+        Ok(path) => path,
+        Err(_) => parent.canonicalize()?.join(file_name),
+    };
+    if !path.starts_with(&root) {
+        bail!("文件路径超出应用文件目录");
+    }
+    Ok(path)
 }
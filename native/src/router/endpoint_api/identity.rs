@@ -0,0 +1,137 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use eyre::{Result, bail, eyre};
+use tokio::sync::RwLock;
+use zeroize::Zeroizing;
+
+const KEYSTORE_EXTENSION: &str = "keystore";
+
+#[derive(Default)]
+pub struct Identities {
+    unlocked: RwLock<HashMap<String, Zeroizing<Vec<u8>>>>,
+}
+impl Identities {
+    pub async fn create(&self, dir: &Path, passphrase: String) -> Result<String> {
+        let keystore = endpoint::create_keystore(passphrase)?;
+        let id = endpoint::get_keystore_id(keystore.clone())?;
+        tokio::fs::create_dir_all(dir).await?;
+        write_keystore(dir, &id, &keystore).await?;
+        Ok(id)
+    }
+    pub async fn import(
+        &self,
+        dir: &Path,
+        secret_key: Vec<u8>,
+        passphrase: String,
+    ) -> Result<String> {
+        let secret_key = Zeroizing::new(secret_key);
+        let keystore = endpoint::lock_secret_key(secret_key.to_vec(), passphrase)?;
+        let id = endpoint::get_keystore_id(keystore.clone())?;
+        if tokio::fs::try_exists(keystore_path(dir, &id)?).await? {
+            bail!("身份{}已存在", id);
+        }
+        tokio::fs::create_dir_all(dir).await?;
+        write_keystore(dir, &id, &keystore).await?;
+        Ok(id)
+    }
+    pub async fn list(&self, dir: &Path) -> Result<Vec<String>> {
+        let mut ids = Vec::new();
+        if !tokio::fs::try_exists(dir).await? {
+            return Ok(ids);
+        }
+        let mut entries = tokio::fs::read_dir(dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|v| v == KEYSTORE_EXTENSION)
+                && let Some(id) = path.file_stem().and_then(|v| v.to_str())
+            {
+                ids.push(id.to_string());
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+    pub async fn import_mnemonic(
+        &self,
+        dir: &Path,
+        mnemonic: String,
+        passphrase: String,
+    ) -> Result<String> {
+        let keystore = endpoint::lock_secret_key(endpoint::import_mnemonic(mnemonic)?, passphrase)?;
+        let id = endpoint::get_keystore_id(keystore.clone())?;
+        if tokio::fs::try_exists(keystore_path(dir, &id)?).await? {
+            bail!("身份{}已存在", id);
+        }
+        tokio::fs::create_dir_all(dir).await?;
+        write_keystore(dir, &id, &keystore).await?;
+        Ok(id)
+    }
+    pub async fn export_mnemonic(
+        &self,
+        dir: &Path,
+        id: String,
+        passphrase: String,
+    ) -> Result<String> {
+        let keystore = read_keystore(dir, &id).await?;
+        endpoint::export_mnemonic(endpoint::unlock_secret_key(keystore, passphrase)?)
+    }
+    pub async fn unlock(&self, dir: &Path, id: String, passphrase: String) -> Result<()> {
+        let keystore = read_keystore(dir, &id).await?;
+        let secret_key = Zeroizing::new(endpoint::unlock_secret_key(keystore, passphrase)?);
+        self.unlocked.write().await.insert(id, secret_key);
+        Ok(())
+    }
+    pub async fn lock(&self, id: &str) {
+        self.unlocked.write().await.remove(id);
+    }
+    pub async fn change_passphrase(
+        &self,
+        dir: &Path,
+        id: String,
+        passphrase: String,
+        new_passphrase: String,
+    ) -> Result<()> {
+        let keystore = read_keystore(dir, &id).await?;
+        let keystore = endpoint::change_passphrase(keystore, passphrase, new_passphrase)?;
+        write_keystore(dir, &id, &keystore).await
+    }
+    pub async fn delete(&self, dir: &Path, id: String, passphrase: String) -> Result<()> {
+        let keystore = read_keystore(dir, &id).await?;
+        let _secret_key = Zeroizing::new(endpoint::unlock_secret_key(keystore, passphrase)?);
+        tokio::fs::remove_file(keystore_path(dir, &id)?).await?;
+        self.lock(&id).await;
+        Ok(())
+    }
+    pub async fn secret_key(&self, id: &str) -> Result<Zeroizing<Vec<u8>>> {
+        self.unlocked
+            .read()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or_else(|| eyre!("身份{}尚未解锁", id))
+    }
+}
+
+fn keystore_path(dir: &Path, id: &str) -> Result<PathBuf> {
+    if id.is_empty() || !id.chars().all(|v| v.is_ascii_alphanumeric()) {
+        bail!("无效的身份ID：{}", id);
+    }
+    Ok(dir.join(id).with_extension(KEYSTORE_EXTENSION))
+}
+async fn read_keystore(dir: &Path, id: &str) -> Result<String> {
+    let path = keystore_path(dir, id)?;
+    if !tokio::fs::try_exists(&path).await? {
+        bail!("身份{}不存在", id);
+    }
+    Ok(tokio::fs::read_to_string(path).await?)
+}
+async fn write_keystore(dir: &Path, id: &str, keystore: &str) -> Result<()> {
+    let path = keystore_path(dir, id)?;
+    let temp_path = path.with_extension("tmp");
+    tokio::fs::write(&temp_path, keystore).await?;
+    tokio::fs::rename(temp_path, path).await?;
+    Ok(())
+}
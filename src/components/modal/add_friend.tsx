@@ -1,20 +1,20 @@
 import { createSignal } from "solid-js";
 import { HomeContext, use_context } from "../context";
-import type { Person } from "~/lib/endpoint/types";
+import type { Profile } from "~/lib/endpoint/types";
 
 export default function AddFriend() {
   const home_store = use_context(HomeContext);
   let search_user_id_input_ref: HTMLInputElement | undefined;
-  const [search_user_result, set_search_user_result] = createSignal<Person>();
+  const [search_user_result, set_search_user_result] = createSignal<Profile>();
   const on_search_user = async () => {
     if (
       search_user_id_input_ref !== undefined &&
       search_user_id_input_ref.value != ""
     ) {
       set_search_user_result(
-        await home_store.endpoint.request_person(
+        (await home_store.endpoint.request_person(
           search_user_id_input_ref.value,
-        ),
+        )) ?? undefined,
       );
     }
   };
@@ -41,7 +41,7 @@ export default function AddFriend() {
             </button>
           </div>
         </div>
-        <div>{search_user_result()?.name}</div>
+        <div>{search_user_result()?.person.name}</div>
       </div>
     </div>
   );
@@ -17,6 +17,9 @@ import { MainContext, use_context } from "../context";
 
 const FormSchema = type({
   user_id: type("string").configure({ message: "请选择一个账户" }),
+  passphrase: type("string >= 8").configure({
+    message: "密码长度不能少于8个字符",
+  }),
 });
 
 export default function Login() {
@@ -40,9 +43,32 @@ export default function Login() {
       }),
     ),
   );
+  const unlock = async (user_id: string, passphrase: string) => {
+    const identities = await main_store.endpoint_module.list_identities();
+    if (!identities.includes(user_id)) {
+      const user = (
+        await main_store.sqlite.query<{ key: Uint8Array | null }>(
+          QueryBuilder.selectFrom("user")
+            .select(["key"])
+            .where("id", "=", user_id)
+            .limit(1)
+            .compile(),
+        )
+      ).at(0);
+      if (!user?.key) throw new Error("没有找到该账户的密钥");
+      await main_store.endpoint_module.import_identity(user.key, passphrase);
+      await main_store.sqlite.execute(
+        QueryBuilder.updateTable("user")
+          .set({ key: null })
+          .where("id", "=", user_id)
+          .compile(),
+      );
+    }
+    await main_store.endpoint_module.unlock_identity(user_id, passphrase);
+  };
   const [preview_avatar, set_preview_avatar] = createSignal<Uint8Array>();
   const form = createForm(() => ({
-    defaultValues: { user_id: undefined as string | undefined },
+    defaultValues: { user_id: undefined as string | undefined, passphrase: "" },
     validators: { onChange: FormSchema },
     onSubmit: ({ value }) => {
       navigate(`/home/${value.user_id}`);
@@ -52,7 +78,7 @@ export default function Login() {
   return (
     <fieldset class="fieldset bg-base-100 border border-base-300 rounded-box p-6 pt-2">
       <legend class="fieldset-legend">登录账户</legend>
-      <span class="text-base-content/60">选择你的账户登录</span>
+      <span class="text-base-content/60">选择你的账户并输入密码登录</span>
       <div class="flex flex-col pt-4">
         <div class="flex justify-center mb-6">
           <div class="avatar">
@@ -134,6 +160,53 @@ export default function Login() {
               )}
             </form.Field>
           </div>
+          <div class="flex flex-col gap-1">
+            <form.Field
+              name="passphrase"
+              validators={{
+                onSubmitAsync: async ({ value, fieldApi }) => {
+                  const user_id = fieldApi.form.getFieldValue("user_id");
+                  if (user_id === undefined) return;
+                  try {
+                    await unlock(user_id, value);
+                  } catch (err) {
+                    return {
+                      message: err instanceof Error ? err.message : String(err),
+                    };
+                  }
+                },
+              }}
+            >
+              {(field) => (
+                <>
+                  <label class="floating-label">
+                    <span>密码</span>
+                    <input
+                      name={field().name}
+                      value={field().state.value}
+                      onBlur={field().handleBlur}
+                      onInput={(e) => field().handleChange(e.target.value)}
+                      type="password"
+                      class="input"
+                      placeholder="密码"
+                    />
+                  </label>
+                  <Show
+                    when={
+                      field().state.meta.isTouched &&
+                      !field().state.meta.isValid
+                    }
+                  >
+                    <For each={field().state.meta.errors}>
+                      {(error) => (
+                        <span class="italic text-error">{error?.message}</span>
+                      )}
+                    </For>
+                  </Show>
+                </>
+              )}
+            </form.Field>
+          </div>
           <button class="btn btn-neutral" disabled={is_submitting()}>
             登录
           </button>
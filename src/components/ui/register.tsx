@@ -8,6 +8,9 @@ import { MainContext, use_context } from "../context";
 
 const FormSchema = type({
   user_name: type("string > 0").configure({ message: "用户名不能为空" }),
+  passphrase: type("string >= 8").configure({
+    message: "密码长度不能少于8个字符",
+  }),
   avatar: "File | null | undefined",
 });
 
@@ -15,20 +18,41 @@ export default function Register() {
   const main_store = use_context(MainContext);
   let avatar_file_input_ref: HTMLInputElement | undefined;
   const form = createForm(() => ({
-    defaultValues: { user_name: "", avatar: null as File | null | undefined },
+    defaultValues: {
+      user_name: "",
+      passphrase: "",
+      avatar: null as File | null | undefined,
+    },
     validators: { onChange: FormSchema },
     onSubmit: async ({ value }) => {
-      const secret_key = await main_store.endpoint_module.generate_secret_key();
-      const user_id =
-        await main_store.endpoint_module.get_secret_key_id(secret_key);
+      const user_id = await main_store.endpoint_module.create_identity(
+        value.passphrase,
+      );
+      await main_store.endpoint_module.unlock_identity(
+        user_id,
+        value.passphrase,
+      );
+      const avatar =
+        value.avatar && new Uint8Array(await value.avatar.arrayBuffer());
+      let avatar_hash: string | undefined;
+      if (avatar) {
+        const endpoint = await main_store.endpoint_module.create_endpoint(
+          user_id,
+          { name: value.user_name, bio: "" },
+        );
+        try {
+          avatar_hash = await endpoint.set_avatar(avatar);
+        } finally {
+          await endpoint.close();
+        }
+      }
       await main_store.sqlite.execute(
         QueryBuilder.insertInto("user")
           .values({
             id: user_id,
-            key: secret_key,
             name: value.user_name,
-            avatar:
-              value.avatar && new Uint8Array(await value.avatar.arrayBuffer()),
+            avatar,
+            avatar_hash,
           })
           .compile(),
       );
@@ -39,7 +63,7 @@ export default function Register() {
   return (
     <fieldset class="fieldset bg-base-100 border border-base-300 rounded-box p-6 pt-2">
       <legend class="fieldset-legend">注册账户</legend>
-      <span class="text-base-content/60">输入用户名注册你的账户</span>
+      <span class="text-base-content/60">输入用户名和密码注册你的账户</span>
       <form
         class="flex flex-col pt-4"
         onSubmit={async (e) => {
@@ -126,6 +150,38 @@ export default function Register() {
               )}
             </form.Field>
           </div>
+          <div class="flex flex-col gap-1">
+            <form.Field name="passphrase">
+              {(field) => (
+                <>
+                  <label class="floating-label">
+                    <span>密码</span>
+                    <input
+                      name={field().name}
+                      value={field().state.value}
+                      onBlur={field().handleBlur}
+                      onInput={(e) => field().handleChange(e.target.value)}
+                      type="password"
+                      class="input"
+                      placeholder="密码"
+                    />
+                  </label>
+                  <Show
+                    when={
+                      field().state.meta.isTouched &&
+                      !field().state.meta.isValid
+                    }
+                  >
+                    <For each={field().state.meta.errors}>
+                      {(error) => (
+                        <span class="italic text-error">{error?.message}</span>
+                      )}
+                    </For>
+                  </Show>
+                </>
+              )}
+            </form.Field>
+          </div>
           <button class="btn btn-neutral" disabled={is_submitting()}>
             注册
           </button>
@@ -1,24 +1,39 @@
-import type { Person, RelayConfig } from "~/lib/endpoint/types";
+import type {
+  EndpointConfig,
+  Person,
+  Profile,
+  ProfileTag,
+  Reply,
+} from "~/lib/endpoint/types";
 import type { Init } from "../interface";
 import type { PersonProtocolEvent } from "./types";
 
 export interface EndpointModule extends Init {
+  create_identity(passphrase: string): Promise<string>;
+  import_identity(secret_key: Uint8Array, passphrase: string): Promise<string>;
+  list_identities(): Promise<string[]>;
+  unlock_identity(id: string, passphrase: string): Promise<void>;
   create_endpoint(
-    secret_key: Uint8Array,
+    identity_id: string,
     person: Person,
-    relay_configs: RelayConfig[],
+    config?: EndpointConfig,
   ): Promise<Endpoint>;
-  generate_secret_key(): Uint8Array | Promise<Uint8Array>;
-  get_secret_key_id(secret_key: Uint8Array): string | Promise<string>;
 }
 
 export interface Endpoint {
   close(): Promise<void>;
   id(): string | Promise<string>;
   person_protocol_next_event(): Promise<PersonProtocolEvent>;
-  person_protocol_event<T>(method: string): Promise<T>;
-  request_person(id: string): Promise<Person>;
-  request_friend(id: string): Promise<boolean>;
-  request_chat(id: string): Promise<bigint | null>;
+  inspect_event(event_id: number): Promise<PersonProtocolEvent>;
+  accept_event(event_id: number): Promise<bigint | null>;
+  reject_event(event_id: number): Promise<void>;
+  set_avatar(avatar: Uint8Array): Promise<string>;
+  fetch_avatar(id: string, hash: string): Promise<Uint8Array>;
+  request_person(id: string, tag?: ProfileTag): Promise<Profile | null>;
+  request_friend(id: string, message: string): Promise<Reply<null>>;
+  request_chat(id: string): Promise<Reply<bigint>>;
+  block(id: string): Promise<void>;
+  unblock(id: string): Promise<boolean>;
+  blocked_list(): Promise<string[]>;
   subscribe_group(ticket: string): Promise<bigint>;
 }
@@ -1,27 +1,38 @@
 import { createTauRPCProxy, type JsonValue } from "~/generated/ipc_bindings";
-import type { Person, RelayConfig } from "./types";
+import type {
+  EndpointConfig,
+  Person,
+  Profile,
+  ProfileTag,
+  Reply,
+} from "./types";
 import type { Endpoint, EndpointModule } from "./interface";
 import type { PersonProtocolEvent } from "./types";
 
 export class EndpointModuleImpl implements EndpointModule {
   init() {}
-  async create_endpoint(
-    secret_key: Uint8Array,
-    person: Person,
-    relay_configs: RelayConfig[],
-  ) {
-    return await EndpointImpl.new(secret_key, person, relay_configs);
-  }
-  async generate_secret_key() {
-    return Uint8Array.from(
-      await createTauRPCProxy().endpoint.generate_secret_key(),
-    );
+  async create_identity(passphrase: string) {
+    return await createTauRPCProxy().endpoint.create_identity(passphrase);
   }
-  async get_secret_key_id(secret_key: Uint8Array) {
-    return await createTauRPCProxy().endpoint.get_secret_key_id(
+  async import_identity(secret_key: Uint8Array, passphrase: string) {
+    return await createTauRPCProxy().endpoint.import_identity(
       Array.from(secret_key),
+      passphrase,
     );
   }
+  async list_identities() {
+    return await createTauRPCProxy().endpoint.list_identities();
+  }
+  async unlock_identity(id: string, passphrase: string) {
+    await createTauRPCProxy().endpoint.unlock_identity(id, passphrase);
+  }
+  async create_endpoint(
+    identity_id: string,
+    person: Person,
+    config?: EndpointConfig,
+  ) {
+    return await EndpointImpl.new(identity_id, person, config);
+  }
 }
 
 export class EndpointImpl implements Endpoint {
@@ -31,15 +42,15 @@ export class EndpointImpl implements Endpoint {
     this.handle = handle;
   }
   static async new(
-    secret_key: Uint8Array,
+    identity_id: string,
     person: Person,
-    relay_configs: RelayConfig[],
+    config: EndpointConfig = {},
   ) {
     return new EndpointImpl(
       await createTauRPCProxy().endpoint.open_endpoint(
-        Array.from(secret_key),
+        identity_id,
         person as unknown as JsonValue,
-        relay_configs as unknown as JsonValue[],
+        config as unknown as JsonValue,
       ),
     );
   }
@@ -52,25 +63,68 @@ export class EndpointImpl implements Endpoint {
   async person_protocol_next_event() {
     return (await createTauRPCProxy().endpoint.person_protocol_next_event(
       this.handle,
-    )) as PersonProtocolEvent;
+    )) as unknown as PersonProtocolEvent;
   }
-  async person_protocol_event<T>(method: string) {
-    return (await createTauRPCProxy().endpoint.person_protocol_event(
+  async inspect_event(event_id: number) {
+    return (await createTauRPCProxy().endpoint.inspect_event(
       this.handle,
-      method,
-    )) as T;
+      BigInt(event_id),
+    )) as unknown as PersonProtocolEvent;
   }
-  async request_person(id: string) {
+  async accept_event(event_id: number) {
+    return await createTauRPCProxy().endpoint.accept_event(
+      this.handle,
+      BigInt(event_id),
+    );
+  }
+  async reject_event(event_id: number) {
+    await createTauRPCProxy().endpoint.reject_event(
+      this.handle,
+      BigInt(event_id),
+    );
+  }
+  async set_avatar(avatar: Uint8Array) {
+    return await createTauRPCProxy().endpoint.set_avatar(
+      this.handle,
+      Array.from(avatar),
+    );
+  }
+  async fetch_avatar(id: string, hash: string) {
+    return Uint8Array.from(
+      await createTauRPCProxy().endpoint.fetch_avatar(this.handle, id, hash),
+    );
+  }
+  async request_person(id: string, tag?: ProfileTag) {
     return (await createTauRPCProxy().endpoint.request_person(
       this.handle,
       id,
-    )) as unknown as Person;
+      (tag ?? null) as unknown as JsonValue | null,
+    )) as unknown as Profile | null;
+  }
+  async request_friend(id: string, message: string) {
+    return (await createTauRPCProxy().endpoint.request_friend(
+      this.handle,
+      id,
+      message,
+    )) as unknown as Reply<null>;
+  }
+  async request_chat(id: string): Promise<Reply<bigint>> {
+    const reply = (await createTauRPCProxy().endpoint.request_chat(
+      this.handle,
+      id,
+    )) as unknown as Reply<number>;
+    return reply.status === "accepted"
+      ? { status: reply.status, value: BigInt(reply.value) }
+      : reply;
+  }
+  async block(id: string) {
+    await createTauRPCProxy().endpoint.block(this.handle, id);
   }
-  async request_friend(id: string) {
-    return await createTauRPCProxy().endpoint.request_friend(this.handle, id);
+  async unblock(id: string) {
+    return await createTauRPCProxy().endpoint.unblock(this.handle, id);
   }
-  async request_chat(id: string) {
-    return await createTauRPCProxy().endpoint.request_chat(this.handle, id);
+  async blocked_list() {
+    return await createTauRPCProxy().endpoint.blocked_list(this.handle);
   }
   async subscribe_group(ticket: string) {
     return await createTauRPCProxy().endpoint.subscribe_group(
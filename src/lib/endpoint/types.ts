@@ -1,12 +1,92 @@
-export type PersonProtocolEvent = "FriendRequest" | "ChatRequest";
+export type Code =
+  | "rejected"
+  | "busy"
+  | "blocked"
+  | "not_accepting"
+  | "rate_limited"
+  | "expired"
+  | "invalid_request"
+  | "too_large"
+  | "internal";
+
+export type Reply<T> =
+  | { status: "accepted"; value: T }
+  | { status: "rejected"; value: Code };
 
 export interface Person {
   name: string;
-  avatar?: Uint8Array;
+  avatar?: string;
   bio: string;
 }
 
+export interface ProfileTag {
+  revision: number;
+  hash: number[];
+}
+
+export interface Profile {
+  person: Person;
+  tag: ProfileTag;
+  signature: number[];
+}
+
+export type Presence = "online" | "away" | "offline";
+
+export interface FriendGreeting {
+  message: string;
+  profile: Profile;
+  timestamp: number;
+}
+
+export type PersonProtocolEvent =
+  | {
+      type: "FriendRequest";
+      id: number;
+      remote_id: string;
+      greeting?: FriendGreeting | null;
+      expired: boolean;
+    }
+  | { type: "ChatRequest"; id: number; remote_id: string; expired: boolean }
+  | {
+      type: "PresenceUpdate";
+      id: number;
+      remote_id: string;
+      presence: Presence;
+    };
+
+export interface PersonProtocolConfig {
+  friend_request_timeout_secs?: number;
+  chat_request_timeout_secs?: number;
+  event_queue_capacity?: number;
+  overflow_policy?: "wait" | "reject_new" | "drop_oldest";
+}
+
 export interface RelayConfig {
   url: string;
   quic_port: number;
 }
+
+export interface AddressLookupConfig {
+  pkarr_publish?: boolean;
+  pkarr_resolve?: boolean;
+  pkarr_relay?: string;
+  dns?: boolean;
+  dns_origin?: string;
+  dns_server?: string;
+  mdns?: boolean;
+  dht?: boolean;
+}
+
+export interface BlobConfig {
+  quota_bytes?: number;
+  gc_interval_secs?: number;
+}
+
+export interface EndpointConfig {
+  address_lookup?: AddressLookupConfig;
+  relay_mode?: "default_and_custom" | "custom_only" | "disabled";
+  relays?: RelayConfig[];
+  relay_only?: boolean;
+  person_protocol?: PersonProtocolConfig;
+  blob?: BlobConfig;
+}
@@ -1,29 +1,59 @@
 import wasm_init, {
-  generate_secret_key as wasm_generate_secret_key,
-  get_secret_key_id as wasm_get_secret_key_id,
+  create_keystore as wasm_create_keystore,
+  get_keystore_id as wasm_get_keystore_id,
+  lock_secret_key as wasm_lock_secret_key,
+  unlock_secret_key as wasm_unlock_secret_key,
   Endpoint as WasmEndpoint,
 } from "@pupu/endpoint";
 import wasm_url from "@pupu/endpoint/endpoint_wasm_bg.wasm?url";
-import type { Person, RelayConfig } from "~/lib/endpoint/types";
+import type {
+  EndpointConfig,
+  Person,
+  Profile,
+  ProfileTag,
+  Reply,
+} from "~/lib/endpoint/types";
 import type { Endpoint, EndpointModule } from "./interface";
 import type { PersonProtocolEvent } from "./types";
 
+const KEYSTORE_PREFIX = "keystore:";
+
 export class EndpointModuleImpl implements EndpointModule {
+  private unlocked = new Map<string, Uint8Array>();
+
   async init() {
     await wasm_init({ module_or_path: wasm_url });
   }
+  private save_keystore(keystore: string) {
+    const id = wasm_get_keystore_id(keystore);
+    localStorage.setItem(KEYSTORE_PREFIX + id, keystore);
+    return id;
+  }
+  async create_identity(passphrase: string) {
+    return this.save_keystore(wasm_create_keystore(passphrase));
+  }
+  async import_identity(secret_key: Uint8Array, passphrase: string) {
+    return this.save_keystore(wasm_lock_secret_key(secret_key, passphrase));
+  }
+  async list_identities() {
+    return Object.keys(localStorage)
+      .filter((v) => v.startsWith(KEYSTORE_PREFIX))
+      .map((v) => v.slice(KEYSTORE_PREFIX.length))
+      .sort();
+  }
+  async unlock_identity(id: string, passphrase: string) {
+    const keystore = localStorage.getItem(KEYSTORE_PREFIX + id);
+    if (keystore === null) throw new Error(`身份${id}不存在`);
+    this.unlocked.set(id, wasm_unlock_secret_key(keystore, passphrase));
+  }
   async create_endpoint(
-    secret_key: Uint8Array,
+    identity_id: string,
     person: Person,
-    relay_configs: RelayConfig[],
+    config?: EndpointConfig,
   ) {
-    return await EndpointImpl.new(secret_key, person, relay_configs);
-  }
-  generate_secret_key() {
-    return wasm_generate_secret_key();
-  }
-  get_secret_key_id(secret_key: Uint8Array) {
-    return wasm_get_secret_key_id(secret_key);
+    const secret_key = this.unlocked.get(identity_id);
+    if (!secret_key) throw new Error(`身份${identity_id}尚未解锁`);
+    return await EndpointImpl.new(secret_key, person, config);
   }
 }
 
@@ -36,10 +66,10 @@ export class EndpointImpl implements Endpoint {
   static async new(
     secret_key: Uint8Array,
     person: Person,
-    relay_configs: RelayConfig[],
+    config: EndpointConfig = {},
   ) {
     return new EndpointImpl(
-      await WasmEndpoint.new(secret_key, person, relay_configs),
+      await WasmEndpoint.new(secret_key, person, config),
     );
   }
   async close() {
@@ -51,18 +81,43 @@ export class EndpointImpl implements Endpoint {
   async person_protocol_next_event() {
     return (await this.endpoint.person_protocol_next_event()) as PersonProtocolEvent;
   }
-  async person_protocol_event<T>(method: string) {
-    return (await this.endpoint.person_protocol_event(method)) as T;
+  async inspect_event(event_id: number) {
+    return this.endpoint.inspect_event(event_id) as PersonProtocolEvent;
+  }
+  async accept_event(event_id: number) {
+    const handle = this.endpoint.accept_event(event_id);
+    return handle != undefined ? BigInt(handle) : null;
+  }
+  async reject_event(event_id: number) {
+    this.endpoint.reject_event(event_id);
+  }
+  async set_avatar(avatar: Uint8Array) {
+    return await this.endpoint.set_avatar(avatar);
+  }
+  async fetch_avatar(id: string, hash: string) {
+    return await this.endpoint.fetch_avatar(id, hash);
+  }
+  async request_person(id: string, tag?: ProfileTag) {
+    const profile = await this.endpoint.request_person(id, tag ?? null);
+    return (profile ?? null) as Profile | null;
+  }
+  async request_friend(id: string, message: string) {
+    return (await this.endpoint.request_friend(id, message)) as Reply<null>;
+  }
+  async request_chat(id: string): Promise<Reply<bigint>> {
+    const reply = (await this.endpoint.request_chat(id)) as Reply<number>;
+    return reply.status === "accepted"
+      ? { status: reply.status, value: BigInt(reply.value) }
+      : reply;
   }
-  async request_person(id: string) {
-    return (await this.endpoint.request_person(id)) as Person;
+  async block(id: string) {
+    this.endpoint.block(id);
   }
-  async request_friend(id: string) {
-    return await this.endpoint.request_friend(id);
+  async unblock(id: string) {
+    return this.endpoint.unblock(id);
   }
-  async request_chat(id: string) {
-    const a = await this.endpoint.request_chat(id);
-    return a != undefined ? (a as unknown as bigint) : null;
+  async blocked_list() {
+    return this.endpoint.blocked_list();
   }
   async subscribe_group(ticket: string) {
     return (await this.endpoint.subscribe_group(ticket)) as unknown as bigint;
@@ -1,37 +1,69 @@
 import type { Endpoint } from "~/lib/endpoint/interface";
+import type { SQLite } from "~/lib/sqlite/interface";
 import type { MainStore } from "./main";
 import type { Store } from "./interface";
 import { QueryBuilder } from "~/lib/query_builder";
 import type { Person } from "~/lib/endpoint/types";
 
 export class HomeStore implements Store {
+  sqlite: SQLite;
+  user_id: string;
   endpoint: Endpoint;
 
-  private constructor(endpoint: Endpoint) {
+  private constructor(sqlite: SQLite, user_id: string, endpoint: Endpoint) {
+    this.sqlite = sqlite;
+    this.user_id = user_id;
     this.endpoint = endpoint;
   }
   static async new(main_store: MainStore, user_id: string) {
     const user = (
-      await main_store.sqlite.query<Person & { key: Uint8Array }>(
+      await main_store.sqlite.query<
+        Omit<Person, "avatar"> & {
+          avatar_hash: string | null;
+        }
+      >(
         QueryBuilder.selectFrom("user")
-          .select(["key", "name", "avatar", "bio"])
+          .select(["name", "avatar_hash", "bio"])
           .where("id", "=", user_id)
           .limit(1)
           .compile(),
       )
     ).at(0);
     if (!user) throw new Error("没有找到相关用户信息");
-    return new HomeStore(
-      await main_store.endpoint_module.create_endpoint(
-        user.key,
-        {
-          name: user.name,
-          avatar: user.avatar,
-          bio: user.bio,
-        },
-        [],
-      ),
+    const endpoint = await main_store.endpoint_module.create_endpoint(
+      user_id,
+      {
+        name: user.name,
+        avatar: user.avatar_hash ?? undefined,
+        bio: user.bio,
+      },
     );
+    const blocked = await main_store.sqlite.query<{ id: string }>(
+      QueryBuilder.selectFrom("blocked")
+        .select("id")
+        .where("user_id", "=", user_id)
+        .compile(),
+    );
+    for (const { id } of blocked) await endpoint.block(id);
+    return new HomeStore(main_store.sqlite, user_id, endpoint);
+  }
+  async block(id: string) {
+    await this.sqlite.execute(
+      QueryBuilder.insertInto("blocked")
+        .values({ user_id: this.user_id, id })
+        .onConflict((oc) => oc.doNothing())
+        .compile(),
+    );
+    await this.endpoint.block(id);
+  }
+  async unblock(id: string) {
+    await this.sqlite.execute(
+      QueryBuilder.deleteFrom("blocked")
+        .where("user_id", "=", this.user_id)
+        .where("id", "=", id)
+        .compile(),
+    );
+    return await this.endpoint.unblock(id);
   }
   async cleanup() {
     await this.endpoint.close();
@@ -1,9 +1,38 @@
+import { CompiledQuery } from "kysely";
 import { EndpointModuleAdapter } from "~/lib/endpoint";
 import type { EndpointModule } from "~/lib/endpoint/interface";
 import { SQLiteModuleAdapter } from "~/lib/sqlite";
 import type { SQLite, SQLiteModule } from "~/lib/sqlite/interface";
 import type { Store } from "./interface";
 
+const MIGRATIONS = [
+  "ALTER TABLE user ADD COLUMN avatar_hash TEXT;",
+  `BEGIN;
+CREATE TABLE "user_new" ("id" TEXT NOT NULL PRIMARY KEY, "key" BLOB, "name" TEXT NOT NULL, "avatar" BLOB, "avatar_hash" TEXT, "bio" TEXT NOT NULL DEFAULT '还没有自我介绍');
+INSERT INTO "user_new" ("id", "key", "name", "avatar", "avatar_hash", "bio") SELECT "id", "key", "name", "avatar", "avatar_hash", "bio" FROM "user";
+DROP TABLE "user";
+ALTER TABLE "user_new" RENAME TO "user";
+CREATE UNIQUE INDEX "user_name_key" ON "user"("name");
+COMMIT;`,
+];
+
+async function migrate(sqlite: SQLite) {
+  const [{ user_version }] = await sqlite.query<{ user_version: number }>(
+    CompiledQuery.raw("PRAGMA user_version"),
+  );
+  const tables = await sqlite.query<{ name: string }>(
+    CompiledQuery.raw(
+      "SELECT name FROM sqlite_schema WHERE type = 'table' AND name = 'user'",
+    ),
+  );
+  if (tables.length > 0) {
+    for (const sql of MIGRATIONS.slice(user_version)) {
+      await sqlite.execute_sql(sql);
+    }
+  }
+  await sqlite.execute_sql(`PRAGMA user_version = ${MIGRATIONS.length};`);
+}
+
 export class MainStore implements Store {
   sqlite_module: SQLiteModule;
   endpoint_module: EndpointModule;
@@ -22,6 +51,7 @@ export class MainStore implements Store {
     const sqlite_module = new SQLiteModuleAdapter();
     await sqlite_module.init();
     const sqlite = await sqlite_module.create_sqlite("data.db");
+    await migrate(sqlite);
     await sqlite.execute_sql(await (await fetch("/db_schema.sql")).text());
     const endpoint_module = new EndpointModuleAdapter();
     await endpoint_module.init();
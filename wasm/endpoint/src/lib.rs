@@ -1,10 +1,16 @@
 mod error;
+mod persistence;
 
-use endpoint::RelayConfig;
+use std::sync::Arc;
+
+use endpoint::StoreBackend;
 use eyre::Result;
 use wasm_bindgen::{JsError, JsValue, prelude::wasm_bindgen};
 
-use crate::error::MapJsError;
+use crate::{
+    error::MapJsError,
+    persistence::{BlobPersistenceAdapter, JsPersistence},
+};
 
 #[wasm_bindgen(start)]
 fn start() {
@@ -19,17 +25,20 @@ impl Endpoint {
     pub async fn new(
         secret_key: Vec<u8>,
         person: JsValue,
-        relay_configs: Vec<JsValue>,
+        config: JsValue,
+        blob_persistence: Option<BlobPersistenceAdapter>,
     ) -> Result<Self, JsError> {
         Ok(Self(
             endpoint::Endpoint::new(
                 secret_key,
                 serde_wasm_bindgen::from_value(person)?,
-                "",
-                relay_configs
-                    .into_iter()
-                    .map(|v| serde_wasm_bindgen::from_value::<RelayConfig>(v))
-                    .collect::<Result<_, _>>()?,
+                match blob_persistence {
+                    Some(adapter) => {
+                        StoreBackend::Persistence(Arc::new(JsPersistence::new(adapter)))
+                    }
+                    None => StoreBackend::Memory,
+                },
+                serde_wasm_bindgen::from_value(config)?,
             )
             .await
             .mje()?,
@@ -42,28 +51,204 @@ impl Endpoint {
     pub fn id(&self) -> String {
         self.0.id()
     }
-    pub async fn person_protocol_next_event(&self) -> Result<String, JsError> {
-        self.0.person_protocol_next_event().await.mje()
+    pub fn network_report(&self) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(&self.0.network_report())?)
+    }
+    pub async fn next_connectivity_event(&self) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(
+            &self.0.next_connectivity_event().await,
+        )?)
+    }
+    pub async fn person_protocol_next_event(&self) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(
+            &self.0.person_protocol_next_event().await.mje()?,
+        )?)
+    }
+    pub fn inspect_event(&self, event_id: usize) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(
+            &self.0.inspect_event(event_id).mje()?,
+        )?)
+    }
+    pub fn accept_event(&self, event_id: usize) -> Result<Option<usize>, JsError> {
+        self.0.accept_event(event_id).mje()
+    }
+    pub fn reject_event(&self, event_id: usize) -> Result<(), JsError> {
+        self.0.reject_event(event_id).mje()
+    }
+    pub fn profile(&self) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(&self.0.profile())?)
+    }
+    pub fn update_person(&self, person: JsValue) -> Result<(), JsError> {
+        self.0
+            .update_person(serde_wasm_bindgen::from_value(person)?)
+            .mje()
+    }
+    pub async fn set_avatar(&self, avatar: Vec<u8>) -> Result<String, JsError> {
+        self.0.set_avatar(avatar).await.mje()
+    }
+    pub async fn fetch_avatar(&self, id: String, hash: String) -> Result<Vec<u8>, JsError> {
+        self.0.fetch_avatar(id, hash).await.mje()
+    }
+    pub async fn add_blob(
+        &self,
+        data: Vec<u8>,
+        owner: JsValue,
+        expires_in_secs: Option<u32>,
+    ) -> Result<String, JsError> {
+        self.0
+            .add_blob(
+                data,
+                serde_wasm_bindgen::from_value(owner)?,
+                expires_in_secs.map(u64::from),
+            )
+            .await
+            .mje()
+    }
+    pub async fn download_blob(
+        &self,
+        ticket: String,
+        owner: JsValue,
+        expires_in_secs: Option<u32>,
+        on_progress: js_sys::Function,
+    ) -> Result<String, JsError> {
+        self.0
+            .download_blob(
+                ticket,
+                serde_wasm_bindgen::from_value(owner)?,
+                expires_in_secs.map(u64::from),
+                |downloaded| {
+                    if let Err(err) =
+                        on_progress.call1(&JsValue::NULL, &JsValue::from_f64(downloaded as f64))
+                    {
+                        log::error!("{:?}", err);
+                    }
+                },
+            )
+            .await
+            .mje()
+    }
+    pub async fn read_blob(&self, hash: String) -> Result<Vec<u8>, JsError> {
+        self.0.read_blob(hash).await.mje()
+    }
+    pub async fn release_blobs(&self, owner: JsValue) -> Result<usize, JsError> {
+        self.0
+            .release_blobs(serde_wasm_bindgen::from_value(owner)?)
+            .await
+            .mje()
     }
-    pub fn person_protocol_event(&self, method: String) -> Result<JsValue, JsError> {
+    pub async fn blob_usage(&self) -> Result<JsValue, JsError> {
         Ok(serde_wasm_bindgen::to_value(
-            &self.0.person_protocol_event(method).mje()?,
+            &self.0.blob_usage().await.mje()?,
         )?)
     }
-    pub async fn request_person(&self, id: String) -> Result<JsValue, JsError> {
+    pub async fn collect_garbage(&self) -> Result<JsValue, JsError> {
         Ok(serde_wasm_bindgen::to_value(
-            &self.0.request_person(id).await.mje()?,
+            &self.0.collect_garbage().await.mje()?,
         )?)
     }
-    pub async fn request_friend(&self, id: String) -> Result<bool, JsError> {
-        self.0.request_friend(id).await.mje()
+    pub async fn request_person(&self, id: String, tag: JsValue) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(
+            &self
+                .0
+                .request_person(id, serde_wasm_bindgen::from_value(tag)?)
+                .await
+                .mje()?,
+        )?)
+    }
+    pub async fn request_friend(&self, id: String, message: String) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(
+            &self.0.request_friend(id, message).await.mje()?,
+        )?)
+    }
+    pub async fn request_chat(&self, id: String) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(
+            &self.0.request_chat(id).await.mje()?,
+        )?)
+    }
+    pub async fn send_message(&self, chat_handle: usize, message: JsValue) -> Result<(), JsError> {
+        self.0
+            .send_message(chat_handle, serde_wasm_bindgen::from_value(message)?)
+            .await
+            .mje()
+    }
+    pub async fn next_message(&self, chat_handle: usize) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(
+            &self.0.next_message(chat_handle).await.mje()?,
+        )?)
     }
-    pub async fn request_chat(&self, id: String) -> Result<Option<usize>, JsError> {
-        self.0.request_chat(id).await.mje()
+    pub fn send_typing(&self, chat_handle: usize, typing: bool) -> Result<(), JsError> {
+        self.0.send_typing(chat_handle, typing).mje()
+    }
+    pub async fn next_typing(&self, chat_handle: usize) -> Result<Option<bool>, JsError> {
+        self.0.next_typing(chat_handle).await.mje()
+    }
+    pub async fn set_presence(
+        &self,
+        presence: JsValue,
+        ids: Vec<String>,
+    ) -> Result<Vec<String>, JsError> {
+        self.0
+            .set_presence(serde_wasm_bindgen::from_value(presence)?, ids)
+            .await
+            .mje()
+    }
+    pub fn chat_stats(&self, chat_handle: usize) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(
+            &self.0.chat_stats(chat_handle).mje()?,
+        )?)
+    }
+    pub fn peer_stats(&self, id: String) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(&self.0.peer_stats(id).mje()?)?)
+    }
+    pub fn close_chat(&self, chat_handle: usize) {
+        self.0.close_chat(chat_handle)
+    }
+    pub fn block(&self, id: String) -> Result<(), JsError> {
+        self.0.block(id).mje()
+    }
+    pub fn unblock(&self, id: String) -> Result<bool, JsError> {
+        self.0.unblock(id).mje()
+    }
+    pub fn blocked_list(&self) -> Vec<String> {
+        self.0.blocked_list()
+    }
+    pub async fn generate_ticket(
+        &self,
+        group_id: String,
+        name: String,
+        bootstrap: Vec<String>,
+        expires_in_secs: Option<u32>,
+    ) -> Result<String, JsError> {
+        self.0
+            .generate_ticket(group_id, name, bootstrap, expires_in_secs.map(u64::from))
+            .await
+            .mje()
     }
     pub async fn subscribe_group(&self, ticket: String) -> Result<usize, JsError> {
         self.0.subscribe_group(ticket).await.mje()
     }
+    pub async fn broadcast(&self, group_handle: usize, message: JsValue) -> Result<(), JsError> {
+        self.0
+            .broadcast(group_handle, serde_wasm_bindgen::from_value(message)?)
+            .await
+            .mje()
+    }
+    pub async fn next_group_message(&self, group_handle: usize) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(
+            &self.0.next_group_message(group_handle).await.mje()?,
+        )?)
+    }
+    pub async fn next_member_event(&self, group_handle: usize) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(
+            &self.0.next_member_event(group_handle).await.mje()?,
+        )?)
+    }
+    pub fn group_dropped_messages(&self, group_handle: usize) -> Result<usize, JsError> {
+        self.0.group_dropped_messages(group_handle).mje()
+    }
+    pub fn leave_group(&self, group_handle: usize) {
+        self.0.leave_group(group_handle)
+    }
 }
 
 #[wasm_bindgen]
@@ -75,10 +260,44 @@ pub fn get_secret_key_id(secret_key: Vec<u8>) -> Result<String, JsError> {
     endpoint::get_secret_key_id(secret_key).mje()
 }
 #[wasm_bindgen]
+pub fn export_mnemonic(secret_key: Vec<u8>) -> Result<String, JsError> {
+    endpoint::export_mnemonic(secret_key).mje()
+}
+#[wasm_bindgen]
+pub fn import_mnemonic(mnemonic: String) -> Result<Vec<u8>, JsError> {
+    endpoint::import_mnemonic(mnemonic).mje()
+}
+#[wasm_bindgen]
+pub fn create_keystore(passphrase: String) -> Result<String, JsError> {
+    endpoint::create_keystore(passphrase).mje()
+}
+#[wasm_bindgen]
+pub fn lock_secret_key(secret_key: Vec<u8>, passphrase: String) -> Result<String, JsError> {
+    endpoint::lock_secret_key(secret_key, passphrase).mje()
+}
+#[wasm_bindgen]
+pub fn unlock_secret_key(keystore: String, passphrase: String) -> Result<Vec<u8>, JsError> {
+    endpoint::unlock_secret_key(keystore, passphrase).mje()
+}
+#[wasm_bindgen]
+pub fn change_passphrase(
+    keystore: String,
+    passphrase: String,
+    new_passphrase: String,
+) -> Result<String, JsError> {
+    endpoint::change_passphrase(keystore, passphrase, new_passphrase).mje()
+}
+#[wasm_bindgen]
+pub fn get_keystore_id(keystore: String) -> Result<String, JsError> {
+    endpoint::get_keystore_id(keystore).mje()
+}
+#[wasm_bindgen]
 pub fn generate_group_id() -> String {
     endpoint::generate_group_id()
 }
 #[wasm_bindgen]
-pub fn generate_ticket(group_id: String, bootstrap: Vec<String>) -> Result<String, JsError> {
-    endpoint::generate_ticket(group_id, bootstrap).mje()
+pub fn verify_ticket(ticket: String) -> Result<JsValue, JsError> {
+    Ok(serde_wasm_bindgen::to_value(
+        &endpoint::verify_ticket(ticket).mje()?,
+    )?)
 }
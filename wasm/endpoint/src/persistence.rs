@@ -0,0 +1,67 @@
+use endpoint::{BlobPersistence, BoxFuture, PersistenceEntries};
+use eyre::{Result, eyre};
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::{JsValue, prelude::wasm_bindgen};
+
+#[wasm_bindgen]
+extern "C" {
+    #[derive(Clone)]
+    pub type BlobPersistenceAdapter;
+    #[wasm_bindgen(method, catch)]
+    async fn entries(this: &BlobPersistenceAdapter) -> Result<JsValue, JsValue>;
+    #[wasm_bindgen(method, catch)]
+    async fn put(
+        this: &BlobPersistenceAdapter,
+        key: String,
+        value: Uint8Array,
+    ) -> Result<JsValue, JsValue>;
+    #[wasm_bindgen(method, catch)]
+    async fn remove(this: &BlobPersistenceAdapter, key: String) -> Result<JsValue, JsValue>;
+}
+
+pub struct JsPersistence(BlobPersistenceAdapter);
+impl JsPersistence {
+    pub fn new(adapter: BlobPersistenceAdapter) -> Self {
+        Self(adapter)
+    }
+}
+impl BlobPersistence for JsPersistence {
+    fn entries(&self) -> BoxFuture<Result<PersistenceEntries>> {
+        let adapter = self.0.clone();
+        Box::pin(async move {
+            let entries = adapter.entries().await.map_err(js_error)?;
+            Array::from(&entries)
+                .iter()
+                .map(|entry| {
+                    let entry = Array::from(&entry);
+                    let key = entry
+                        .get(0)
+                        .as_string()
+                        .ok_or_else(|| eyre!("持久化存储的键不是字符串"))?;
+                    Ok((key, Uint8Array::new(&entry.get(1)).to_vec()))
+                })
+                .collect()
+        })
+    }
+    fn put(&self, key: String, value: Vec<u8>) -> BoxFuture<Result<()>> {
+        let adapter = self.0.clone();
+        Box::pin(async move {
+            adapter
+                .put(key, Uint8Array::from(value.as_slice()))
+                .await
+                .map_err(js_error)?;
+            Ok(())
+        })
+    }
+    fn remove(&self, key: String) -> BoxFuture<Result<()>> {
+        let adapter = self.0.clone();
+        Box::pin(async move {
+            adapter.remove(key).await.map_err(js_error)?;
+            Ok(())
+        })
+    }
+}
+
+fn js_error(err: JsValue) -> eyre::Report {
+    eyre!("持久化存储操作失败：{:?}", err)
+}